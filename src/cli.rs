@@ -0,0 +1,93 @@
+// src/cli.rs
+// Argumentos de línea de comandos. El parser es manual: son pocas opciones
+
+// Opciones reconocidas en la línea de comandos
+#[derive(Debug, Default, PartialEq)]
+pub struct CliArgs {
+    pub path: Option<String>,   // EPUB a abrir (None = selector de recientes, "-" = stdin)
+    pub chapter: Option<usize>, // --chapter N: imprimir ese capítulo (basado en 1) y salir
+    pub json: bool,             // --json: salida en JSON para los modos no interactivos
+    pub dump_text: bool,        // --dump-text: imprimir el texto de todo el libro y salir
+    pub validate: bool,         // --validate: comprobar la estructura del EPUB y salir
+    pub start: Option<usize>,   // --start N: abrir la interfaz en ese capítulo del spine (basado en 1)
+    pub no_resume: bool,        // --no-resume: no volver a la posición guardada del libro
+}
+
+impl CliArgs {
+    // Parsea los argumentos sin el nombre del programa
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut cli = CliArgs::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--chapter" => {
+                    let value = args.next()
+                        .ok_or("--chapter necesita un número de capítulo")?;
+                    let chapter = value.parse().ok()
+                        .filter(|&chapter| chapter > 0)
+                        .ok_or_else(|| format!("Número de capítulo no válido: {}", value))?;
+                    cli.chapter = Some(chapter);
+                }
+                "--start" => {
+                    let value = args.next()
+                        .ok_or("--start necesita un número de capítulo")?;
+                    let chapter = value.parse().ok()
+                        .filter(|&chapter| chapter > 0)
+                        .ok_or_else(|| format!("Número de capítulo no válido: {}", value))?;
+                    cli.start = Some(chapter);
+                }
+                "--no-resume" => cli.no_resume = true,
+                "--json" => cli.json = true,
+                "--dump-text" => cli.dump_text = true,
+                "--validate" => cli.validate = true,
+                _ if arg.starts_with("--") => return Err(format!("Opción desconocida: {}", arg)),
+                _ if cli.path.is_none() => cli.path = Some(arg),
+                _ => return Err(format!("Argumento inesperado: {}", arg)),
+            }
+        }
+
+        if cli.path.is_none() && cli.chapter.is_some() {
+            return Err("--chapter necesita la ruta de un EPUB".to_string());
+        }
+        if cli.path.is_none() && cli.dump_text {
+            return Err("--dump-text necesita la ruta de un EPUB (o - para leerlo de stdin)".to_string());
+        }
+        if cli.chapter.is_some() && cli.dump_text {
+            return Err("--chapter y --dump-text no se pueden usar a la vez".to_string());
+        }
+        if cli.validate && (cli.chapter.is_some() || cli.dump_text) {
+            return Err("--validate no se puede usar junto con --chapter ni --dump-text".to_string());
+        }
+        if cli.path.is_none() && cli.validate {
+            return Err("--validate necesita la ruta de un EPUB (o - para leerlo de stdin)".to_string());
+        }
+        // La interfaz necesita el terminal, así que stdin solo vale en los modos no interactivos
+        if cli.reads_stdin() && !cli.is_batch() {
+            return Err("Leer el EPUB de stdin (-) requiere --dump-text, --chapter o --validate".to_string());
+        }
+        if cli.start.is_some() && cli.path.is_none() {
+            return Err("--start necesita la ruta de un EPUB".to_string());
+        }
+        if (cli.start.is_some() || cli.no_resume) && cli.is_batch() {
+            return Err("--start y --no-resume solo se usan con la interfaz, no con --chapter, --dump-text ni --validate".to_string());
+        }
+        if cli.json && cli.chapter.is_none() {
+            return Err("--json solo se puede usar junto con --chapter".to_string());
+        }
+        Ok(cli)
+    }
+
+    // El EPUB llega por la entrada estándar ("-" como ruta)
+    pub fn reads_stdin(&self) -> bool {
+        self.path.as_deref() == Some("-")
+    }
+
+    // Modo sin interfaz: imprime y sale
+    pub fn is_batch(&self) -> bool {
+        self.chapter.is_some() || self.dump_text || self.validate
+    }
+}
+
+pub fn usage(program: &str) -> String {
+    format!("Uso: {} [ruta_al_archivo.epub | -] [--chapter N [--json] | --dump-text | --validate | --start N] [--no-resume]", program)
+}
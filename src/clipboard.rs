@@ -0,0 +1,28 @@
+// src/clipboard.rs
+// Envoltorio sobre arboard para copiar texto al portapapeles del sistema.
+// En entornos sin portapapeles (sesiones SSH, sin servidor gráfico) devuelve
+// un error legible en lugar de fallar, para mostrarlo en la barra de estado.
+
+pub struct Clipboard {
+    // Se crea al primer uso y se mantiene vivo: en X11 el contenido copiado
+    // desaparece si se destruye el objeto que lo posee
+    inner: Option<arboard::Clipboard>,
+}
+
+impl Clipboard {
+    pub fn new() -> Self {
+        Clipboard { inner: None }
+    }
+
+    // Copia el texto al portapapeles
+    pub fn copy(&mut self, text: &str) -> Result<(), String> {
+        if self.inner.is_none() {
+            let clipboard = arboard::Clipboard::new()
+                .map_err(|e| format!("Portapapeles no disponible: {}", e))?;
+            self.inner = Some(clipboard);
+        }
+        let clipboard = self.inner.as_mut().expect("portapapeles inicializado");
+        clipboard.set_text(text.to_string())
+            .map_err(|e| format!("No se pudo copiar al portapapeles: {}", e))
+    }
+}
@@ -0,0 +1,75 @@
+// src/config.rs
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use serde::Deserialize;
+
+const CONFIG_FILE: &str = "config.toml";
+
+// Configuración del usuario, leída una vez al arrancar. Los campos que falten en el
+// archivo toman su valor por defecto, así que un archivo parcial (o vacío) es válido
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub theme: String,        // Nombre del tema inicial ("dark" o "light")
+    pub width: usize,         // Ancho máximo del texto (0 = todo el ancho)
+    pub auto_advance: bool,   // Pasar de capítulo al desplazarse más allá de los bordes
+    pub style: String,        // "rich" usa negrita/cursiva del terminal, "markdown" muestra *marcadores*, "plain" nada
+    pub wpm: u32,             // Velocidad inicial del auto-scroll (palabras por minuto)
+    pub scroll_step: u16,     // Líneas que avanzan j/k (0 se trata como 1)
+    pub paragraph_style: String, // "spaced" (línea en blanco entre párrafos) o "indent" (sangría)
+    pub list_style: String,   // Marcador de las listas: "dash" (-) o "bullet" (•)
+    pub show_links: bool,     // Mostrar el destino de los enlaces tras su texto
+    pub images: bool,         // Mostrar portada e imágenes como arte de bloques (requiere color de 24 bits)
+    pub scrollbar: bool,      // Mostrar la barra de desplazamiento a la derecha del texto y la TOC
+    pub language: String,     // Idioma de la interfaz ("es" o "en"); vacío = según LANG
+    pub aliases: HashMap<String, String>, // Tabla [aliases]: alias -> comando, p. ej. h = "goto 1"
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        AppConfig {
+            theme: "dark".to_string(),
+            width: 0,
+            auto_advance: true,
+            style: "rich".to_string(),
+            wpm: 250,
+            scroll_step: 1,
+            paragraph_style: "spaced".to_string(),
+            list_style: "dash".to_string(),
+            show_links: false,
+            images: false,
+            scrollbar: true,
+            language: String::new(),
+            aliases: HashMap::new(),
+        }
+    }
+}
+
+impl AppConfig {
+    // Carga ~/.config/epub_reader/config.toml. Si no existe se usan los valores por
+    // defecto; si no se puede leer o parsear se devuelve el motivo
+    pub fn load() -> Result<Self, String> {
+        let Some(path) = config_dir().map(|dir| dir.join(CONFIG_FILE)) else {
+            return Ok(AppConfig::default());
+        };
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(AppConfig::default()),
+            Err(e) => return Err(format!("No se pudo leer {}: {}", path.display(), e)),
+        };
+        toml::from_str(&content)
+            .map_err(|e| format!("Configuración no válida en {}: {}", path.display(), e))
+    }
+}
+
+// Directorio de configuración: $XDG_CONFIG_HOME/epub_reader o ~/.config/epub_reader
+pub fn config_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("epub_reader"))
+}
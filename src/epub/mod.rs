@@ -1,19 +1,40 @@
 
 // src/epub/mod.rs
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::fs::File;
-use std::io::{Read, BufReader};
+use std::io::{self, Read, Seek, SeekFrom, BufReader, Cursor};
 use std::path::{Path, PathBuf};
 use zip::ZipArchive;
+use encoding_rs::{Encoding, UTF_8};
 use roxmltree::{Document, Node};
 
 use crate::metadata::Metadata;
-use crate::navigation::{Navigator, TocEntry};
+use crate::navigation::{Landmark, Navigator, PageEntry, TocEntry};
 use crate::errors::EpubError;
+use crate::path_utils;
 
 const CONTAINER_PATH: &str = "META-INF/container.xml";
 const OPF_MIME_TYPE: &str = "application/oebps-package+xml";
 
+// Dirección de avance de páginas declarada en <spine page-progression-direction="...">
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReadingDirection {
+    #[default]
+    Ltr,
+    Rtl, // Manga, árabe, hebreo...
+}
+
+// Documento de navegación del que se obtuvo la tabla de contenidos
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TocSource {
+    Nav,   // nav.xhtml (EPUB 3)
+    Ncx,   // toc.ncx (EPUB 2)
+    Spine, // Sin TOC en el libro: generada a partir del spine
+    #[default]
+    None,
+}
+
 // Representa un item en el manifiesto del OPF
 #[derive(Debug, Clone)]
 pub struct ManifestItem {
@@ -25,82 +46,276 @@ pub struct ManifestItem {
     pub properties: Option<String>, // Para identificar el archivo NAV en EPUB3
 }
 
-// Estructura principal que contiene la información parseada del EPUB
+// Origen de los bytes del EPUB: el archivo abierto en disco o una copia en memoria
 #[derive(Debug)]
-pub struct EpubDocument {
-    // Mantenemos el archivo abierto para leer contenido bajo demanda
-    // Nota: Esto significa que el archivo EPUB no debe ser movido/eliminado
-    // mientras el programa se ejecuta. Una alternativa es leer todo en memoria
-    // o reabrir el archivo cada vez (menos eficiente).
-    // Usamos BufReader para mejorar eficiencia de lectura.
-    archive: ZipArchive<BufReader<File>>,
+pub enum EpubSource {
+    File(BufReader<File>),
+    Memory(Cursor<Arc<[u8]>>), // Compartido con los ChapterReader del mismo libro
+}
+
+impl Read for EpubSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            EpubSource::File(reader) => reader.read(buf),
+            EpubSource::Memory(cursor) => cursor.read(buf),
+        }
+    }
+}
+
+impl Seek for EpubSource {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            EpubSource::File(reader) => reader.seek(pos),
+            EpubSource::Memory(cursor) => cursor.seek(pos),
+        }
+    }
+}
+
+// Acceso propio al ZIP de un libro ya abierto, que se puede mover a otro hilo
+pub struct ChapterReader {
+    archive: ZipArchive<EpubSource>,
+}
+
+impl ChapterReader {
+    // Igual que EpubDocument::read_chapter_content
+    pub fn read_chapter_content(&mut self, href: &str) -> Result<String, EpubError> {
+        read_entry_to_string(&mut self.archive, href)
+    }
+
+    // Igual que EpubDocument::read_entry
+    pub fn read_entry(&mut self, path: &str) -> Result<Vec<u8>, EpubError> {
+        read_entry_bytes(&mut self.archive, path)
+    }
+}
+
+// Estructura principal que contiene la información parseada del EPUB.
+// Es genérica sobre el lector del ZIP para poder abrir EPUBs en memoria (tests, stdin...)
+#[derive(Debug)]
+pub struct EpubDocument<R: Read + Seek = EpubSource> {
+    // Con EpubSource::File mantenemos el archivo abierto para leer contenido bajo demanda,
+    // así que el EPUB no debe moverse/eliminarse mientras el programa se ejecuta.
+    // from_bytes evita esa restricción a cambio de tener el archivo completo en memoria.
+    archive: ZipArchive<R>,
     pub metadata: Metadata,
     pub manifest: HashMap<String, ManifestItem>,
     pub spine_ids: Vec<String>, // IDs de los items del spine en orden
-    pub toc: Vec<TocEntry>,
-    #[allow(dead_code)]
-    opf_path: PathBuf, // Ruta del archivo OPF dentro del ZIP
-    root_path: String, // Directorio que contiene el OPF (para resolver rutas relativas)
+    pub spine_linear: Vec<bool>, // Paralelo a spine_ids: false si el itemref tiene linear="no"
+    pub toc: Vec<TocEntry>, // Árbol de la TOC (entradas de primer nivel con sus subentradas)
+    pub page_list: Vec<PageEntry>, // Páginas impresas (nav epub:type="page-list"), si existen
+    pub landmarks: Vec<Landmark>, // Puntos de referencia (nav landmarks o <guide> en EPUB 2)
+    pub reading_direction: ReadingDirection, // Ltr si el spine no indica otra cosa
+    pub epub_version: Option<String>, // Atributo version de <package> ("2.0", "3.0"...)
+    pub opf_path: PathBuf, // Ruta del archivo OPF dentro del ZIP
+    pub root_path: String, // Directorio que contiene el OPF (para resolver rutas relativas)
+    pub toc_source: TocSource,
+    pub warnings: Vec<String>, // Problemas encontrados al parsear que no impiden leer el libro
+    source_path: Option<PathBuf>, // Archivo del que se abrió, para abrir lectores independientes
+    source_bytes: Option<Arc<[u8]>>, // O bytes con los que se abrió (from_bytes), con el mismo fin
 }
 
 impl EpubDocument {
     // Función principal para abrir y parsear un archivo EPUB
     pub fn open(path: &Path) -> Result<Self, EpubError> {
+        Self::open_with_progress(path, |_| {})
+    }
+
+    // Igual que open, pero avisa de cada paso del parseo ("Parseando el OPF"...) para
+    // poder mostrar el progreso con libros grandes o almacenamiento lento
+    pub fn open_with_progress(path: &Path, progress: impl FnMut(&str)) -> Result<Self, EpubError> {
         let file = File::open(path)?;
         let buf_reader = BufReader::new(file); // Envuelve File en BufReader
-        let mut archive = ZipArchive::new(buf_reader)?;
+        let mut doc = Self::from_reader_with_progress(EpubSource::File(buf_reader), progress)?;
+        doc.source_path = Some(path.to_path_buf());
+        Ok(doc)
+    }
+
+    // Lee el archivo completo a memoria y lo parsea sin mantener el archivo abierto.
+    // Útil para EPUBs en unidades de red o archivos temporales
+    #[allow(dead_code)]
+    pub fn open_in_memory(path: &Path) -> Result<Self, EpubError> {
+        Self::from_bytes(std::fs::read(path)?)
+    }
+
+    // Abre otro acceso al ZIP del libro para leer capítulos desde otro hilo (la precarga).
+    // El archivo se vuelve a abrir en vez de compartir el del documento, cuya posición de
+    // lectura no se puede repartir entre dos hilos; un libro en memoria comparte los bytes
+    pub fn chapter_reader(&self) -> Result<ChapterReader, EpubError> {
+        let source = match (&self.source_bytes, &self.source_path) {
+            (Some(bytes), _) => EpubSource::Memory(Cursor::new(Arc::clone(bytes))),
+            (None, Some(path)) => EpubSource::File(BufReader::new(File::open(path)?)),
+            (None, None) => {
+                return Err(EpubError::ContentReadError("El EPUB no se abrió con open ni con from_bytes".to_string()));
+            }
+        };
+        Ok(ChapterReader { archive: ZipArchive::new(source)? })
+    }
+
+    // Parsea un EPUB ya cargado en memoria (leído de stdin, por ejemplo), sin ningún
+    // archivo abierto detrás
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, EpubError> {
+        let bytes: Arc<[u8]> = bytes.into();
+        let mut doc = Self::from_reader(EpubSource::Memory(Cursor::new(Arc::clone(&bytes))))?;
+        doc.source_bytes = Some(bytes);
+        Ok(doc)
+    }
+}
+
+impl<R: Read + Seek> EpubDocument<R> {
+    // Parsea un EPUB desde cualquier lector con acceso aleatorio (archivo, Cursor<Vec<u8>>...)
+    pub fn from_reader(reader: R) -> Result<Self, EpubError> {
+        Self::from_reader_with_progress(reader, |_| {})
+    }
+
+    // from_reader con aviso de progreso en cada paso principal
+    pub fn from_reader_with_progress(reader: R, mut progress: impl FnMut(&str)) -> Result<Self, EpubError> {
+        progress("Abriendo el archivo ZIP");
+        let mut archive = ZipArchive::new(reader)?;
 
         // 1. Parsear container.xml para encontrar el archivo OPF
-        let opf_path_str = parse_container(&mut archive)?;
+        progress("Leyendo container.xml");
+        let mut warnings = Vec::new();
+        let opf_path_str = parse_container(&mut archive, &mut warnings)?;
         let opf_path = PathBuf::from(&opf_path_str);
 
         // Determinar el directorio raíz (el que contiene el OPF)
-        let root_path = opf_path.parent()
-            .and_then(|p| p.to_str())
-            .unwrap_or("")
-            .to_string();
+        let root_path = path_utils::parent(&opf_path_str).to_string();
 
         // 2. Leer y parsear el archivo OPF
+        progress("Parseando el OPF");
         let opf_content = read_entry_to_string(&mut archive, &opf_path_str)?;
         let opf_doc = Document::parse(&opf_content)?;
 
-        let package_node = if opf_doc.root_element().tag_name().name() == "package" {
-            opf_doc.root_element()
-        } else {
-            opf_doc.root_element()
-                .children().find(|n| n.tag_name().name() == "package")
-                .ok_or(EpubError::MissingPackageElement)?
-        };
+        // Normalmente <package> es la raíz, pero algunos generadores lo envuelven en otro elemento
+        let package_node = opf_doc.descendants()
+            .find(|n| n.is_element() && n.tag_name().name() == "package")
+            .ok_or(EpubError::MissingPackageElement)?;
+
+        let epub_version = package_node.attribute("version")
+            .map(str::trim)
+            .filter(|version| !version.is_empty())
+            .map(str::to_string);
 
         // 3. Parsear Metadatos
-        let metadata_node = package_node.children().find(|n| n.tag_name().name() == "metadata")
+        progress("Parseando los metadatos");
+        let metadata_node = find_child(package_node, "metadata")
             .ok_or(EpubError::MissingMetadataElement)?;
         let metadata = Metadata::parse(metadata_node)?;
 
         // 4. Parsear Manifiesto
-        let manifest_node = package_node.children().find(|n| n.tag_name().name() == "manifest")
+        progress("Parseando el manifiesto y el spine");
+        let manifest_node = find_child(package_node, "manifest")
             .ok_or(EpubError::MissingManifestElement)?;
         let manifest = parse_manifest(manifest_node)?;
 
         // 5. Parsear Spine
-        let spine_node = package_node.children().find(|n| n.tag_name().name() == "spine")
+        let spine_node = find_child(package_node, "spine")
             .ok_or(EpubError::MissingSpineElement)?;
-        let spine_ids = parse_spine(spine_node)?;
+        let (spine_ids, spine_linear): (Vec<String>, Vec<bool>) =
+            parse_spine(spine_node)?.into_iter().unzip();
+        let reading_direction = match spine_node.attribute("page-progression-direction") {
+            Some(direction) if direction.trim().eq_ignore_ascii_case("rtl") => ReadingDirection::Rtl,
+            _ => ReadingDirection::Ltr, // Ausente, "ltr" o "default"
+        };
 
         // 6. Encontrar y parsear la Tabla de Contenidos (TOC)
-        let toc = parse_toc(&mut archive, &manifest, &root_path, spine_node)?;
+        progress("Parseando la tabla de contenidos");
+        let NavContent { mut toc, page_list, mut landmarks, source: mut toc_source } =
+            parse_toc(&mut archive, &manifest, &root_path, spine_node, &mut warnings)?;
+        if toc.is_empty() {
+            toc = synthesize_toc(&manifest, &root_path, &spine_ids, &spine_linear);
+            if !toc.is_empty() {
+                toc_source = TocSource::Spine;
+                warnings.push("Se ha generado una tabla de contenidos a partir del spine".to_string());
+            }
+        }
+        if landmarks.is_empty() {
+            // EPUB 2: <guide><reference type="text" .../> equivale a las landmarks del nav
+            if let Some(guide_node) = find_child(package_node, "guide") {
+                landmarks = parse_guide(guide_node, &opf_path_str);
+            }
+        }
 
         Ok(EpubDocument {
             archive,
             metadata,
             manifest,
             spine_ids,
+            spine_linear,
             toc,
+            page_list,
+            landmarks,
+            reading_direction,
+            epub_version,
             opf_path,
             root_path,
+            toc_source,
+            warnings,
+            source_path: None,
+            source_bytes: None,
         })
     }
 
+    // Lee como bytes cualquier archivo del EPUB (imágenes, fuentes, CSS...) por su ruta
+    // completa dentro del ZIP, como la de los capítulos o una resuelta con
+    // path_utils::resolve. Admite las mismas variantes de %XX y mayúsculas que los capítulos
+    pub fn read_entry(&mut self, path: &str) -> Result<Vec<u8>, EpubError> {
+        read_entry_bytes(&mut self.archive, path).map_err(|e| match e {
+            EpubError::Zip(zip::result::ZipError::FileNotFound) => {
+                EpubError::ContentReadError(format!("Archivo no encontrado en el ZIP: {}", path))
+            }
+            other_err => other_err,
+        })
+    }
+
+    // Lee como bytes cualquier archivo del EPUB (imágenes, fuentes, CSS...). El href es
+    // relativo al directorio del OPF, como los del manifiesto; uno que empieza por '/' es
+    // absoluto desde la raíz del ZIP. Se descarta el #fragmento
+    #[allow(dead_code)]
+    pub fn read_resource(&mut self, href: &str) -> Result<Vec<u8>, EpubError> {
+        let path = path_utils::join(&self.root_path, path_utils::strip_fragment(href));
+        self.read_entry(&path)
+    }
+
+    // Tamaño sin comprimir de un archivo del EPUB, sin leerlo; None si no está en el ZIP
+    pub fn entry_size(&mut self, path: &str) -> Option<u64> {
+        let name = find_entry_name(&self.archive, path)?;
+        self.archive.by_name(&name).ok().map(|entry| entry.size())
+    }
+
+    // Problemas de estructura que hacen el libro inválido (para --validate): spine vacío o
+    // con items que no están en el manifiesto, archivos del manifiesto que faltan en el ZIP
+    // y ausencia de tabla de contenidos. Las advertencias del parseo están en `warnings`
+    pub fn validation_errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.spine_ids.is_empty() {
+            errors.push("El spine no contiene ningún item".to_string());
+        }
+        for id in self.spine_ids.iter().filter(|id| !self.manifest.contains_key(*id)) {
+            errors.push(format!("El item '{}' del spine no está en el manifiesto", id));
+        }
+        let mut items: Vec<&ManifestItem> = self.manifest.values().collect();
+        items.sort_by(|a, b| a.href.cmp(&b.href));
+        for item in items {
+            let path = path_utils::join(&self.root_path, path_utils::strip_fragment(&item.href));
+            if find_entry_name(&self.archive, &path).is_none() {
+                errors.push(format!("El archivo '{}' del manifiesto (id '{}') no está en el ZIP", path, item.id));
+            }
+        }
+        if self.toc.is_empty() || self.toc_source == TocSource::Spine {
+            errors.push("El libro no tiene tabla de contenidos (nav.xhtml o toc.ncx)".to_string());
+        }
+        errors
+    }
+
+    // Ruta completa de la imagen de portada: el item con properties="cover-image" (EPUB 3)
+    // o el que indica <meta name="cover"> (EPUB 2)
+    pub fn cover_href(&self) -> Option<String> {
+        let item = self.manifest.values()
+            .find(|item| item.properties.as_deref().is_some_and(|props| props.split_whitespace().any(|p| p == "cover-image")))
+            .or_else(|| self.metadata.cover_id.as_deref().and_then(|id| self.manifest.get(id)))?;
+        Some(path_utils::join(&self.root_path, &item.href))
+    }
+
     // Lee el contenido de un capítulo (archivo XHTML) por su ID del spine
     // Mut borrow of self.archive needed here.
     pub fn read_chapter_content(&mut self, href: &str) -> Result<String, EpubError> {
@@ -113,10 +328,30 @@ impl EpubDocument {
             })
     }
 
+    // Recorre los capítulos en el orden del spine devolviendo (href, XHTML sin procesar).
+    // Cada capítulo se lee del ZIP al pedirlo, así que el iterador mantiene el préstamo
+    // mutable del documento (y de su archivo) mientras está vivo; un capítulo que no
+    // se puede leer produce un Err y el recorrido continúa con el siguiente
+    pub fn chapters(&mut self) -> impl Iterator<Item = Result<(String, String), EpubError>> + '_ {
+        let hrefs: Vec<Result<String, EpubError>> = self.spine_ids.iter()
+            .map(|id| {
+                self.manifest.get(id)
+                    .map(|item| path_utils::join(&self.root_path, &item.href))
+                    .ok_or_else(|| EpubError::ManifestItemNotFound(id.clone()))
+            })
+            .collect();
+        hrefs.into_iter().map(move |href| {
+            let href = href?;
+            let content = self.read_chapter_content(&href)?;
+            Ok((href, content))
+        })
+    }
+
     // Crea el navegador
      pub fn create_navigator(&self) -> Navigator {
         Navigator::new(
             self.spine_ids.clone(),
+            self.spine_linear.clone(),
             self.toc.clone(),
             self.manifest.clone(),
             self.root_path.clone(),
@@ -127,15 +362,72 @@ impl EpubDocument {
 
 // --- Funciones auxiliares de parsing ---
 
-fn read_entry_to_string<R: Read + std::io::Seek>(archive: &mut ZipArchive<R>, path: &str) -> Result<String, EpubError> {
-    let mut entry = archive.by_name(path)?;
-    let mut content = String::new();
-    entry.read_to_string(&mut content)?;
-    Ok(content)
+fn read_entry_to_string<R: Read + Seek>(archive: &mut ZipArchive<R>, path: &str) -> Result<String, EpubError> {
+    Ok(decode_text(&read_entry_bytes(archive, path)?))
 }
 
+fn read_entry_bytes<R: Read + Seek>(archive: &mut ZipArchive<R>, path: &str) -> Result<Vec<u8>, EpubError> {
+    let name = find_entry_name(archive, path).ok_or(zip::result::ZipError::FileNotFound)?;
+    let mut entry = archive.by_name(&name)?;
+    let mut bytes = Vec::new();
+    entry.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+// Decodifica el contenido de un archivo del EPUB. Por orden: BOM (que se elimina),
+// codificación declarada (<?xml encoding="..."?> o <meta charset>) y, si no hay
+// ninguna, UTF-8 sustituyendo las secuencias inválidas por U+FFFD
+fn decode_text(bytes: &[u8]) -> String {
+    let (encoding, bom_length) = Encoding::for_bom(bytes)
+        .or_else(|| declared_encoding(bytes).map(|encoding| (encoding, 0)))
+        .unwrap_or((UTF_8, 0));
+    let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_length..]);
+    text.into_owned()
+}
+
+// Codificación declarada al principio del documento: encoding="..." en la declaración
+// XML o charset=... en un <meta> de HTML. Solo se miran los primeros bytes
+fn declared_encoding(bytes: &[u8]) -> Option<&'static Encoding> {
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(1024)]).to_ascii_lowercase();
+    ["encoding=", "charset="].iter().find_map(|key| {
+        let value = head[head.find(key)? + key.len()..].trim_start_matches(['"', '\'', ' ']);
+        let end = value.find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':' | '.')))?;
+        // Si la declaración se pudo leer como ASCII, el archivo no es UTF-16 aunque lo diga
+        Encoding::for_label(&value.as_bytes()[..end]).map(Encoding::output_encoding)
+    })
+}
 
-fn parse_container<R: Read + std::io::Seek>(archive: &mut ZipArchive<R>) -> Result<String, EpubError> {
+
+// Nombre real de una entrada del ZIP. Muchos EPUBs referencian los archivos con %XX
+// (p. ej. %20 para espacios, incluso %2F para las barras), con una barra inicial o con
+// otras mayúsculas que las del ZIP, y algunos ZIP guardan los nombres como "/OEBPS/..."
+// o "./OEBPS/...": se prueba el nombre exacto, luego decodificado, luego ambos lados
+// normalizados y por último sin distinguir mayúsculas
+fn find_entry_name<R: Read + Seek>(archive: &ZipArchive<R>, path: &str) -> Option<String> {
+    let decoded = path_utils::percent_decode(path);
+    let canonical = |name: &str| path_utils::normalize(name).trim_start_matches('/').to_string();
+    let wanted = canonical(&decoded);
+    let lowercase = wanted.to_lowercase();
+    let mut normalized = None;
+    let mut case_insensitive = None;
+    for name in archive.file_names() {
+        if name == path || name == decoded {
+            return Some(name.to_string());
+        }
+        if normalized.is_some() {
+            continue;
+        }
+        let name_canonical = canonical(name);
+        if name_canonical == wanted {
+            normalized = Some(name.to_string());
+        } else if case_insensitive.is_none() && name_canonical.to_lowercase() == lowercase {
+            case_insensitive = Some(name.to_string());
+        }
+    }
+    normalized.or(case_insensitive)
+}
+
+fn parse_container<R: Read + Seek>(archive: &mut ZipArchive<R>, warnings: &mut Vec<String>) -> Result<String, EpubError> {
     let container_content = read_entry_to_string(archive, CONTAINER_PATH)
         .map_err(|_| EpubError::MissingContainerXml)?; // Error específico si container.xml falta
 
@@ -150,19 +442,23 @@ fn parse_container<R: Read + std::io::Seek>(archive: &mut ZipArchive<R>) -> Resu
     // Validar que el tipo sea el esperado (opcional pero bueno)
     let media_type = rootfile_node.attribute("media-type");
     if media_type != Some(OPF_MIME_TYPE) {
-        // Podrías loggear un warning aquí si quieres
-        eprintln!("Advertencia: media-type del rootfile no es '{}', es {:?}. Continuando...", OPF_MIME_TYPE, media_type);
+        warnings.push(format!("El media-type del rootfile no es '{}', es {:?}", OPF_MIME_TYPE, media_type));
     }
 
     Ok(opf_path.to_string())
 }
 
+// Primer hijo con ese nombre local, sea cual sea su prefijo (opf:, dc:...) o namespace
+fn find_child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| n.is_element() && n.tag_name().name() == name)
+}
+
 fn parse_manifest(manifest_node: Node) -> Result<HashMap<String, ManifestItem>, EpubError> {
     let mut manifest = HashMap::new();
     for item_node in manifest_node.children().filter(|n| n.tag_name().name() == "item") {
-        let id = item_node.attribute("id").ok_or_else(|| EpubError::XmlTextExtractionError)?.to_string();
-        let href = item_node.attribute("href").ok_or_else(|| EpubError::XmlTextExtractionError)?.to_string();
-        let media_type = item_node.attribute("media-type").ok_or_else(|| EpubError::XmlTextExtractionError)?.to_string();
+        let id = item_node.attribute("id").ok_or(EpubError::XmlTextExtractionError)?.to_string();
+        let href = item_node.attribute("href").ok_or(EpubError::XmlTextExtractionError)?.to_string();
+        let media_type = item_node.attribute("media-type").ok_or(EpubError::XmlTextExtractionError)?.to_string();
         let properties = item_node.attribute("properties").map(str::to_string);
 
         manifest.insert(id.clone(), ManifestItem { id, href, media_type, properties });
@@ -170,39 +466,57 @@ fn parse_manifest(manifest_node: Node) -> Result<HashMap<String, ManifestItem>,
     Ok(manifest)
 }
 
-fn parse_spine(spine_node: Node) -> Result<Vec<String>, EpubError> {
-    let mut spine_ids = Vec::new();
+// Devuelve los idref del spine en orden junto con su atributo linear
+// (ausente equivale a linear="yes")
+fn parse_spine(spine_node: Node) -> Result<Vec<(String, bool)>, EpubError> {
+    let mut spine_items = Vec::new();
     for itemref_node in spine_node.children().filter(|n| n.tag_name().name() == "itemref") {
-        let idref = itemref_node.attribute("idref").ok_or_else(|| EpubError::XmlTextExtractionError)?.to_string();
-        spine_ids.push(idref);
+        let idref = itemref_node.attribute("idref").ok_or(EpubError::XmlTextExtractionError)?.to_string();
+        let linear = itemref_node.attribute("linear") != Some("no");
+        spine_items.push((idref, linear));
     }
-    Ok(spine_ids)
+    Ok(spine_items)
 }
 
-fn parse_toc<R: Read + std::io::Seek>(
+// Lo que se obtiene de los documentos de navegación: TOC, lista de páginas y landmarks
+#[derive(Debug, Default)]
+struct NavContent {
+    toc: Vec<TocEntry>,
+    page_list: Vec<PageEntry>,
+    landmarks: Vec<Landmark>,
+    source: TocSource,
+}
+
+fn parse_toc<R: Read + Seek>(
     archive: &mut ZipArchive<R>,
     manifest: &HashMap<String, ManifestItem>,
     root_path: &str,
     spine_node: Node, // Necesario para buscar el ID del toc.ncx
-) -> Result<Vec<TocEntry>, EpubError> {
+    warnings: &mut Vec<String>,
+) -> Result<NavContent, EpubError> {
     // Estrategia:
     // 1. Buscar en el manifiesto un item con properties="nav" (EPUB 3).
     // 2. Si no se encuentra, buscar el ID del toc.ncx en el atributo 'toc' del <spine>.
     // 3. Si se encuentra, buscar ese ID en el manifiesto para obtener el href.
     // 4. Parsear el archivo encontrado (nav.xhtml o toc.ncx).
+    // La lista de páginas y las landmarks solo se obtienen del nav.xhtml, aunque el TOC venga del NCX.
+    let mut nav = NavContent::default();
 
     // Buscar Nav XHTML (EPUB 3)
     if let Some(nav_item) = manifest.values().find(|item| item.properties.as_deref() == Some("nav")) {
-        let nav_href = build_full_path(root_path, &nav_item.href);
+        let nav_href = path_utils::join(root_path, &nav_item.href);
         match read_entry_to_string(archive, &nav_href) {
              Ok(nav_content) => {
-                 match parse_nav_xhtml(&nav_content, root_path, &nav_href) {
-                    Ok(toc) if !toc.is_empty() => return Ok(toc),
-                    Ok(_) => eprintln!("Advertencia: Se encontró nav.xhtml pero no contenía entradas de TOC válidas."),
-                    Err(e) => eprintln!("Advertencia: Error al parsear nav.xhtml: {}", e),
+                 match parse_nav_xhtml(&nav_content, &nav_href) {
+                    Ok(parsed) if !parsed.toc.is_empty() => return Ok(parsed),
+                    Ok(parsed) => {
+                        nav = parsed;
+                        warnings.push("Se encontró nav.xhtml pero no contenía entradas de TOC válidas".to_string());
+                    }
+                    Err(e) => warnings.push(format!("Error al parsear nav.xhtml: {}", e)),
                  }
              }
-             Err(e) => eprintln!("Advertencia: No se pudo leer el archivo nav referenciado: {} ({})", nav_href, e),
+             Err(e) => warnings.push(format!("No se pudo leer el archivo nav referenciado: {} ({})", nav_href, e)),
         }
     }
 
@@ -210,40 +524,41 @@ fn parse_toc<R: Read + std::io::Seek>(
     // Buscar toc.ncx (EPUB 2)
     if let Some(toc_id) = spine_node.attribute("toc") {
         if let Some(ncx_item) = manifest.get(toc_id) {
-             let ncx_href = build_full_path(root_path, &ncx_item.href);
+             let ncx_href = path_utils::join(root_path, &ncx_item.href);
             match read_entry_to_string(archive, &ncx_href) {
                 Ok(ncx_content) => {
-                    match parse_ncx(&ncx_content, root_path, &ncx_href) {
-                         Ok(toc) if !toc.is_empty() => return Ok(toc),
-                         Ok(_) => eprintln!("Advertencia: Se encontró toc.ncx pero no contenía entradas válidas."),
-                         Err(e) => eprintln!("Advertencia: Error al parsear toc.ncx: {}", e),
+                    match parse_ncx(&ncx_content, &ncx_href) {
+                         Ok(toc) if !toc.is_empty() => return Ok(NavContent { toc, source: TocSource::Ncx, ..nav }),
+                         Ok(_) => warnings.push("Se encontró toc.ncx pero no contenía entradas válidas".to_string()),
+                         Err(e) => warnings.push(format!("Error al parsear toc.ncx: {}", e)),
                     }
                 }
-                 Err(e) => eprintln!("Advertencia: No se pudo leer el archivo NCX referenciado: {} ({})", ncx_href, e),
+                 Err(e) => warnings.push(format!("No se pudo leer el archivo NCX referenciado: {} ({})", ncx_href, e)),
             }
         } else {
-             eprintln!("Advertencia: El ID del TOC '{}' del spine no se encontró en el manifiesto.", toc_id);
+             warnings.push(format!("El ID del TOC '{}' del spine no se encontró en el manifiesto", toc_id));
         }
     }
 
     // Si no se encontró ninguno de los dos
-     eprintln!("Advertencia: No se pudo encontrar o parsear un archivo de tabla de contenidos (nav.xhtml o toc.ncx). La navegación por TOC no estará disponible.");
-     Ok(Vec::new()) // Devolver un TOC vacío si no se encuentra
+     warnings.push("No se encontró una tabla de contenidos válida (nav.xhtml o toc.ncx)".to_string());
+     Ok(NavContent { source: TocSource::None, ..nav }) // Devolver un TOC vacío si no se encuentra
      // Err(EpubError::TocNotFound) // O devolver error si prefieres que falle
 }
 
 
-// Parsea un archivo nav.xhtml (EPUB 3)
-fn parse_nav_xhtml(content: &str, root_path: &str, nav_file_path: &str) -> Result<Vec<TocEntry>, EpubError> {
+// Parsea un archivo nav.xhtml (EPUB 3): devuelve la TOC, la lista de páginas (page-list) y las landmarks
+fn parse_nav_xhtml(content: &str, nav_file_path: &str) -> Result<NavContent, EpubError> {
     let document = scraper::Html::parse_document(content);
     // Selector robusto: busca un <nav> con epub:type="toc", luego su <ol>, luego <li><a>
     // O directamente busca los enlaces dentro del <nav epub:type="toc">
-     let nav_toc_selector = scraper::Selector::parse(r#"nav[epub|type="toc"] ol li a"#)
+    // El parser HTML conserva "epub:type" como nombre literal del atributo, así que se escapa
+    // el ':' en lugar de usar la sintaxis de namespaces (epub|type), que scraper rechaza
+     let nav_toc_selector = scraper::Selector::parse(r#"nav[epub\:type="toc"] ol li a"#)
         .or_else(|_| scraper::Selector::parse(r#"nav[type="toc"] ol li a"#)) // Sin namespace
         .map_err(|e| EpubError::TocParseError(format!("Selector nav inválido: {}", e)))?;
 
     let mut toc = Vec::new();
-    let nav_base_path = Path::new(nav_file_path).parent().unwrap_or_else(|| Path::new(""));
 
     for element in document.select(&nav_toc_selector) {
         if let Some(href_attr) = element.value().attr("href") {
@@ -252,41 +567,153 @@ fn parse_nav_xhtml(content: &str, root_path: &str, nav_file_path: &str) -> Resul
                 continue; // Ignora entradas sin etiqueta o href
             }
 
-            // Resuelve la ruta relativa al archivo nav.xhtml, luego relativa al root_path
-            let resolved_href = resolve_relative_path(nav_base_path, href_attr);
-             // Normalizamos para comparar con manifest hrefs (que son relativos a root_path)
-            let final_href = build_full_path(root_path, &resolved_href.to_string_lossy());
+            // Resuelve la ruta relativa al archivo nav.xhtml; como nav_file_path ya es
+            // la ruta completa dentro del ZIP, el resultado también lo es
+            let final_href = path_utils::resolve(nav_file_path, href_attr);
+
 
+            // Nivel de anidamiento: número de <li> que contienen el enlace (1 = nivel superior)
+            let depth = element.ancestors()
+                .filter_map(scraper::ElementRef::wrap)
+                .filter(|ancestor| ancestor.value().name() == "li")
+                .count()
+                .max(1);
 
             toc.push(TocEntry {
                 label,
                 href: final_href, // Guardamos la ruta normalizada relativa al root
+                fragment: href_attr.split_once('#').map(|(_, fragment)| fragment.to_string()),
                 id: element.value().id().map(str::to_string),
+                depth,
+                children: Vec::new(),
             });
         }
     }
+    let toc = nest_toc(toc);
 
-    Ok(toc)
+    // Lista de páginas impresas: <nav epub:type="page-list"><ol><li><a href="...">12</a>
+    let page_list_selector = scraper::Selector::parse(r#"nav[epub\:type="page-list"] a"#)
+        .map_err(|e| EpubError::TocParseError(format!("Selector page-list inválido: {}", e)))?;
+    let mut page_list = Vec::new();
+    for element in document.select(&page_list_selector) {
+        let label = element.text().collect::<String>().trim().to_string();
+        let Some(href_attr) = element.value().attr("href").filter(|h| !h.is_empty()) else {
+            continue;
+        };
+        if label.is_empty() {
+            continue;
+        }
+        page_list.push(PageEntry {
+            label,
+            href: path_utils::resolve(nav_file_path, href_attr),
+            fragment: href_attr.split_once('#').map(|(_, fragment)| fragment.to_string()),
+        });
+    }
+
+    // Puntos de referencia: <nav epub:type="landmarks"><ol><li><a epub:type="bodymatter" href="...">
+    let landmarks_selector = scraper::Selector::parse(r#"nav[epub\:type="landmarks"] a"#)
+        .map_err(|e| EpubError::TocParseError(format!("Selector landmarks inválido: {}", e)))?;
+    let landmarks = document.select(&landmarks_selector)
+        .filter_map(|element| {
+            let href_attr = element.value().attr("href").filter(|h| !h.is_empty())?;
+            Some(Landmark {
+                label: element.text().collect::<String>().trim().to_string(),
+                kind: element.value().attr("epub:type").unwrap_or("").trim().to_string(),
+                href: path_utils::resolve(nav_file_path, href_attr),
+                fragment: href_attr.split_once('#').map(|(_, fragment)| fragment.to_string()),
+            })
+        })
+        .collect();
+
+    Ok(NavContent { toc, page_list, landmarks, source: TocSource::Nav })
+}
+
+// Parsea el <guide> del OPF (EPUB 2). type="text" marca el inicio del texto, como
+// la landmark "bodymatter" de EPUB 3, así que se traduce a ese tipo
+fn parse_guide(guide_node: Node, opf_file_path: &str) -> Vec<Landmark> {
+    guide_node.children()
+        .filter(|n| n.tag_name().name() == "reference")
+        .filter_map(|reference| {
+            let href_attr = reference.attribute("href").filter(|h| !h.is_empty())?;
+            let kind = match reference.attribute("type").unwrap_or("").trim() {
+                "text" => "bodymatter",
+                other => other,
+            };
+            Some(Landmark {
+                label: reference.attribute("title").unwrap_or(kind).trim().to_string(),
+                kind: kind.to_string(),
+                href: path_utils::resolve(opf_file_path, href_attr),
+                fragment: href_attr.split_once('#').map(|(_, fragment)| fragment.to_string()),
+            })
+        })
+        .collect()
+}
+
+// TOC de reserva para libros sin nav.xhtml ni toc.ncx: una entrada por cada item lineal
+// del spine. Las entradas no tienen etiqueta para no leer todo el libro al abrirlo: la UI
+// las nombra "Capítulo N" en su idioma y les pone el <title> de cada archivo al cargarlo
+fn synthesize_toc(
+    manifest: &HashMap<String, ManifestItem>,
+    root_path: &str,
+    spine_ids: &[String],
+    spine_linear: &[bool],
+) -> Vec<TocEntry> {
+    spine_ids.iter()
+        .zip(spine_linear.iter().chain(std::iter::repeat(&true)))
+        .filter(|(_, &linear)| linear)
+        .filter_map(|(id, _)| manifest.get(id))
+        .map(|item| {
+            let href = path_utils::join(root_path, path_utils::strip_fragment(&item.href));
+            TocEntry { label: String::new(), href, fragment: None, id: None, depth: 1, children: Vec::new() }
+        })
+        .collect()
+}
+
+// Convierte entradas en orden de documento con su profundidad en un árbol: cada entrada
+// cuelga de la última anterior con menos profundidad
+fn nest_toc(flat: Vec<TocEntry>) -> Vec<TocEntry> {
+    // Cadena de entradas abiertas, de la raíz a la más profunda
+    fn close_last(open: &mut Vec<TocEntry>, roots: &mut Vec<TocEntry>) {
+        if let Some(entry) = open.pop() {
+            match open.last_mut() {
+                Some(parent) => parent.children.push(entry),
+                None => roots.push(entry),
+            }
+        }
+    }
+    let mut roots = Vec::new();
+    let mut open: Vec<TocEntry> = Vec::new();
+    for entry in flat {
+        while open.last().is_some_and(|last| last.depth >= entry.depth) {
+            close_last(&mut open, &mut roots);
+        }
+        open.push(entry);
+    }
+    while !open.is_empty() {
+        close_last(&mut open, &mut roots);
+    }
+    roots
 }
 
 // Parsea un archivo toc.ncx (EPUB 2)
-fn parse_ncx(content: &str, root_path: &str, ncx_file_path: &str) -> Result<Vec<TocEntry>, EpubError> {
+fn parse_ncx(content: &str, ncx_file_path: &str) -> Result<Vec<TocEntry>, EpubError> {
     let doc = Document::parse(content)?;
     let nav_map_node = doc.descendants()
         .find(|n| n.tag_name().name() == "navMap")
         .ok_or_else(|| EpubError::TocParseError("No se encontró <navMap> en NCX".to_string()))?;
 
     let mut toc = Vec::new();
-    let ncx_base_path = Path::new(ncx_file_path).parent().unwrap_or_else(|| Path::new(""));
-
-    parse_navpoints(nav_map_node, &mut toc, ncx_base_path, root_path);
+    parse_navpoints(nav_map_node, &mut toc, ncx_file_path, 1);
 
     Ok(toc)
 }
 
 
 // Función recursiva para parsear navPoints en NCX
-fn parse_navpoints(parent_node: Node, toc: &mut Vec<TocEntry>, ncx_base_path: &Path, root_path: &str) {
+// depth es el nivel de los navPoint hijos de parent_node (1 = nivel superior). Los
+// navPoint anidados quedan como hijos de su entrada; los de un navPoint sin etiqueta o
+// sin destino suben al nivel de este
+fn parse_navpoints(parent_node: Node, toc: &mut Vec<TocEntry>, ncx_file_path: &str, depth: usize) {
     for node in parent_node.children() {
         if node.tag_name().name() == "navPoint" {
              let id = node.attribute("id").map(str::to_string);
@@ -302,92 +729,468 @@ fn parse_navpoints(parent_node: Node, toc: &mut Vec<TocEntry>, ncx_base_path: &P
             if let Some(content_node) = node.children().find(|n| n.tag_name().name() == "content") {
                 if let Some(src_attr) = content_node.attribute("src") {
                     if !label.is_empty() && !src_attr.is_empty() {
-                         // Resuelve la ruta relativa al archivo ncx, luego relativa al root_path
-                         let resolved_href = resolve_relative_path(ncx_base_path, src_attr);
-                         let final_href = build_full_path(root_path, &resolved_href.to_string_lossy());
+                         // Resuelve la ruta relativa al archivo ncx (ya es una ruta completa del ZIP)
+                         let final_href = path_utils::resolve(ncx_file_path, src_attr);
 
+                         let mut children = Vec::new();
+                         parse_navpoints(node, &mut children, ncx_file_path, depth + 1);
                          toc.push(TocEntry {
                              label,
                              href: final_href,
+                             fragment: src_attr.split_once('#').map(|(_, fragment)| fragment.to_string()),
                              id,
+                             depth,
+                             children,
                          });
+                         continue;
                     }
                 }
             }
-             // Recursivamente procesar hijos navPoint anidados (si los hubiera)
-             parse_navpoints(node, toc, ncx_base_path, root_path);
+             // Entrada no válida: sus hijos navPoint (si los hubiera) ocupan su lugar
+             parse_navpoints(node, toc, ncx_file_path, depth);
         }
     }
 }
 
 
-// --- Funciones auxiliares de rutas ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::FileOptions;
 
-// Construye una ruta completa dentro del ZIP relativa al directorio raíz del EPUB.
-// root_path: Directorio que contiene el archivo OPF (e.g., "OEBPS").
-// relative_href: El href encontrado en OPF o TOC (e.g., "chapter1.xhtml" o "../Text/chapter1.xhtml").
-fn build_full_path(root_path: &str, relative_href: &str) -> String {
-    if root_path.is_empty() {
-        // Si OPF está en la raíz, el href es la ruta final
-        normalize_path_simple(relative_href)
-    } else {
-        // Une el directorio raíz con el href relativo
-         let combined = format!("{}/{}", root_path, relative_href);
-         normalize_path_simple(&combined)
+    // Construye un EPUB mínimo en memoria a partir de pares (ruta, contenido)
+    fn build_epub(entries: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in entries {
+            writer.start_file(*name, FileOptions::default()).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
     }
-}
 
+    const CONTAINER: &str = r#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>"#;
 
-// Resuelve una ruta relativa (`relative_path`) basándose en la ruta del archivo que la contiene (`base_path_str`)
-// Devuelve una PathBuf que representa la ruta resuelta.
-// NOTA: Esta es una implementación simple. Librerías como `url` o `path_clean` serían más robustas.
-fn resolve_relative_path(base_path: &Path, relative_path: &str) -> PathBuf {
-     // Si la ruta relativa empieza con '/', es absoluta desde la raíz del "servidor" (zip)
-     if relative_path.starts_with('/') {
-        return PathBuf::from(relative_path.trim_start_matches('/'));
-     }
-    let mut current = base_path.to_path_buf();
-    // Elimina fragmentos (#...) de la ruta relativa si existen
-    let relative_path_no_fragment = relative_path.split('#').next().unwrap_or("");
-
-    for component in relative_path_no_fragment.split('/') {
-        match component {
-            "." | "" => {} // Ignorar
-            ".." => { current.pop(); } // Subir un nivel
-            _ => current.push(component), // Añadir componente
-        }
+    const OPF: &str = r#"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:title>Libro de prueba</dc:title>
+  </metadata>
+  <manifest>
+    <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+    <item id="ch1" href="Text/ch1.xhtml" media-type="application/xhtml+xml"/>
+    <item id="notes" href="Text/notes.xhtml" media-type="application/xhtml+xml"/>
+  </manifest>
+  <spine>
+    <itemref idref="ch1"/>
+    <itemref idref="notes" linear="no"/>
+  </spine>
+</package>"#;
+
+    const NAV: &str = r#"<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<body><nav epub:type="toc"><ol><li><a href="Text/ch1.xhtml">Capítulo 1</a></li></ol></nav></body>
+</html>"#;
+
+    #[test]
+    fn from_reader_parses_minimal_epub() {
+        let bytes = build_epub(&[
+            (CONTAINER_PATH, CONTAINER),
+            ("OEBPS/content.opf", OPF),
+            ("OEBPS/nav.xhtml", NAV),
+            ("OEBPS/Text/ch1.xhtml", "<html><body><p>Hola</p></body></html>"),
+        ]);
+        let mut doc = EpubDocument::from_reader(Cursor::new(bytes)).unwrap();
+
+        assert_eq!(doc.metadata.title.as_deref(), Some("Libro de prueba"));
+        assert_eq!(doc.spine_ids, vec!["ch1", "notes"]);
+        assert_eq!(doc.spine_linear, vec![true, false]);
+        assert_eq!(doc.reading_direction, ReadingDirection::Ltr);
+        assert_eq!(doc.epub_version.as_deref(), Some("3.0"));
+        assert_eq!(doc.toc.len(), 1);
+        assert_eq!(doc.toc[0].href, "OEBPS/Text/ch1.xhtml");
+        assert_eq!(doc.toc_source, TocSource::Nav);
+        assert!(doc.warnings.is_empty());
+
+        let navigator = doc.create_navigator();
+        let href = navigator.current_chapter_href().unwrap();
+        assert!(doc.read_chapter_content(&href).unwrap().contains("Hola"));
     }
-     //println!("Resolving relative: base='{}', rel='{}', result='{}'", base_path.display(), relative_path, current.display());
-    current
-}
 
+    #[test]
+    fn chapter_reader_reads_from_another_thread() {
+        let bytes = build_epub(&[
+            (CONTAINER_PATH, CONTAINER),
+            ("OEBPS/content.opf", OPF),
+            ("OEBPS/nav.xhtml", NAV),
+            ("OEBPS/Text/ch1.xhtml", "<html><body><p>Hola</p></body></html>"),
+        ]);
+        // Sin archivo ni bytes de origen no hay con qué abrir otro lector
+        assert!(EpubDocument::from_reader(EpubSource::Memory(Cursor::new(bytes.clone().into()))).unwrap().chapter_reader().is_err());
+        let mut reader = EpubDocument::from_bytes(bytes.clone()).unwrap().chapter_reader().unwrap();
+        assert!(reader.read_chapter_content("OEBPS/Text/ch1.xhtml").unwrap().contains("Hola"));
 
-// Función helper (simplificada) para normalizar rutas (maneja "./" y "../", asume separador '/')
-// Una librería como `path_clean` o `lexiclean` sería más robusta
-fn normalize_path_simple(path_str: &str) -> String {
-    let mut components: Vec<&str> = Vec::new();
-    // Crear una variable vinculada para extender el tiempo de vida
-    let normalized = path_str.replace('\\', "/");
-    
-    for component in normalized.split('/') {
-        match component {
-            "." | "" => {} // Ignorar componente actual o vacío
-            ".." => {
-                // No subir más allá de la raíz
-                if !components.is_empty() && components.last() != Some(&"..") {
-                    components.pop();
-                }
-            }
-            _ => components.push(component), // Añadir componente normal
-        }
+        let path = std::env::temp_dir().join(format!("epub_reader_prefetch_{}.epub", std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        let mut reader = EpubDocument::open(&path).unwrap().chapter_reader().unwrap();
+        let content = std::thread::spawn(move || reader.read_chapter_content("OEBPS/Text/ch1.xhtml"))
+            .join()
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(content.unwrap().contains("Hola"));
+    }
+
+    #[test]
+    fn open_in_memory_does_not_need_the_file_afterwards() {
+        let bytes = build_epub(&[
+            (CONTAINER_PATH, CONTAINER),
+            ("OEBPS/content.opf", OPF),
+            ("OEBPS/nav.xhtml", NAV),
+            ("OEBPS/Text/ch1.xhtml", "<html><body><p>Hola</p></body></html>"),
+        ]);
+        let path = std::env::temp_dir().join(format!("epub_reader_in_memory_{}.epub", std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        let mut doc = EpubDocument::open_in_memory(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(doc.read_chapter_content("OEBPS/Text/ch1.xhtml").unwrap().contains("Hola"));
+        let mut reader = doc.chapter_reader().unwrap();
+        assert!(reader.read_chapter_content("OEBPS/Text/ch1.xhtml").unwrap().contains("Hola"));
+    }
+
+    #[test]
+    fn chapters_follow_spine_order_and_report_missing_files() {
+        let bytes = build_epub(&[
+            (CONTAINER_PATH, CONTAINER),
+            ("OEBPS/content.opf", OPF),
+            ("OEBPS/nav.xhtml", NAV),
+            ("OEBPS/Text/ch1.xhtml", "<html><body><p>Hola</p></body></html>"),
+        ]);
+        let mut doc = EpubDocument::from_reader(Cursor::new(bytes)).unwrap();
+        let chapters: Vec<_> = doc.chapters().collect();
+
+        assert_eq!(chapters.len(), 2);
+        let (href, content) = chapters[0].as_ref().unwrap();
+        assert_eq!(href, "OEBPS/Text/ch1.xhtml");
+        assert!(content.contains("Hola"));
+        // notes.xhtml está en el spine pero no en el ZIP
+        assert!(matches!(chapters[1], Err(EpubError::ContentReadError(_))));
+    }
+
+    #[test]
+    fn reads_chapters_despite_case_and_percent_encoding_mismatches() {
+        let bytes = build_epub(&[
+            (CONTAINER_PATH, CONTAINER),
+            ("OEBPS/content.opf", OPF),
+            ("OEBPS/Text/Mi capítulo.xhtml", "<p>Codificado</p>"),
+            ("OEBPS/Text/CH1.XHTML", "<p>Mayúsculas</p>"),
+        ]);
+        let mut doc = EpubDocument::from_reader(Cursor::new(bytes)).unwrap();
+        let encoded = doc.read_chapter_content("OEBPS/Text/Mi%20cap%C3%ADtulo.xhtml").unwrap();
+        assert!(encoded.contains("Codificado"));
+        assert!(doc.read_chapter_content("OEBPS/Text/ch1.xhtml").unwrap().contains("Mayúsculas"));
+        assert!(doc.read_chapter_content("OEBPS/Text/otro.xhtml").is_err());
+    }
+
+    #[test]
+    fn reads_entries_with_leading_slashes_and_encoded_separators() {
+        let bytes = build_epub(&[
+            (CONTAINER_PATH, CONTAINER),
+            ("OEBPS/content.opf", OPF),
+            ("OEBPS/Text/ch1.xhtml", "<p>Uno</p>"),
+            ("/OEBPS/Text/ch2.xhtml", "<p>Dos</p>"),
+        ]);
+        let mut doc = EpubDocument::from_reader(Cursor::new(bytes)).unwrap();
+        assert!(doc.read_chapter_content("/OEBPS/Text/ch1.xhtml").unwrap().contains("Uno"));
+        assert!(doc.read_chapter_content("OEBPS%2FText%2Fch1.xhtml").unwrap().contains("Uno"));
+        assert!(doc.read_chapter_content("OEBPS/Text/ch2.xhtml").unwrap().contains("Dos"));
+    }
+
+    #[test]
+    fn read_resource_resolves_hrefs_like_the_manifest() {
+        let bytes = build_epub(&[
+            (CONTAINER_PATH, CONTAINER),
+            ("OEBPS/content.opf", OPF),
+            ("OEBPS/Images/Mi portada.png", "\u{89}PNG"),
+            ("META-INF/fonts.css", "@font-face {}"),
+        ]);
+        let mut doc = EpubDocument::from_reader(Cursor::new(bytes)).unwrap();
+        assert_eq!(doc.read_resource("Images/Mi%20portada.png").unwrap(), "\u{89}PNG".as_bytes());
+        assert_eq!(doc.read_resource("Text/../Images/mi portada.PNG#x").unwrap(), "\u{89}PNG".as_bytes());
+        assert_eq!(doc.read_resource("/META-INF/fonts.css").unwrap(), b"@font-face {}");
+        assert!(matches!(doc.read_resource("Images/otra.png"), Err(EpubError::ContentReadError(_))));
+    }
+
+    #[test]
+    fn read_entry_finds_resources_by_zip_path() {
+        let bytes = build_epub(&[
+            (CONTAINER_PATH, CONTAINER),
+            ("OEBPS/content.opf", OPF),
+            ("OEBPS/Images/Mi portada.png", "\u{89}PNG"),
+            ("META-INF/fonts.css", "@font-face {}"),
+        ]);
+        let mut doc = EpubDocument::from_reader(Cursor::new(bytes)).unwrap();
+        assert_eq!(doc.read_entry("OEBPS/Images/Mi%20portada.png").unwrap(), "\u{89}PNG".as_bytes());
+        assert_eq!(doc.read_entry("OEBPS/Images/mi portada.PNG").unwrap(), "\u{89}PNG".as_bytes());
+        assert_eq!(doc.read_entry("META-INF/fonts.css").unwrap(), b"@font-face {}");
+        assert!(matches!(doc.read_entry("OEBPS/Images/otra.png"), Err(EpubError::ContentReadError(_))));
+        assert_eq!(doc.entry_size("META-INF/fonts.css"), Some(13));
+        assert_eq!(doc.entry_size("OEBPS/Images/otra.png"), None);
+    }
+
+    #[test]
+    fn decodes_bom_declared_charsets_and_invalid_utf8() {
+        assert_eq!(decode_text("\u{feff}<p>Hola</p>".as_bytes()), "<p>Hola</p>");
+        let latin1 = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><p>Espa\xf1a</p>";
+        assert!(decode_text(latin1).ends_with("<p>España</p>"));
+        // roxmltree no rechaza la declaración una vez decodificado el texto
+        assert!(Document::parse(&decode_text(latin1)).is_ok());
+        let meta = b"<html><head><meta charset=\"windows-1252\"/></head><body>\x93cita\x94</body></html>";
+        assert!(decode_text(meta).contains("\u{201c}cita\u{201d}"));
+        assert_eq!(decode_text(b"<p>a\xffb</p>"), "<p>a\u{fffd}b</p>");
+    }
+
+    #[test]
+    fn parses_prefixed_opf_nested_in_another_element() {
+        let opf = r#"<?xml version="1.0"?>
+<wrapper>
+<opf:package xmlns:opf="http://www.idpf.org/2007/opf" xmlns:dc="http://purl.org/dc/elements/1.1/" version="2.0">
+  <opf:metadata>
+    <dc:title>Con prefijos</dc:title>
+    <dc:creator opf:role="aut">Autora</dc:creator>
+  </opf:metadata>
+  <opf:manifest>
+    <opf:item id="ch1" href="Text/ch1.xhtml" media-type="application/xhtml+xml"/>
+  </opf:manifest>
+  <opf:spine><opf:itemref idref="ch1"/></opf:spine>
+</opf:package>
+</wrapper>"#;
+        let bytes = build_epub(&[
+            (CONTAINER_PATH, CONTAINER),
+            ("OEBPS/content.opf", opf),
+            ("OEBPS/Text/ch1.xhtml", "<html><body><p>Hola</p></body></html>"),
+        ]);
+        let doc = EpubDocument::from_reader(Cursor::new(bytes)).unwrap();
+        assert_eq!(doc.metadata.title.as_deref(), Some("Con prefijos"));
+        assert_eq!(doc.metadata.creators[0].role.as_deref(), Some("aut"));
+        assert_eq!(doc.epub_version.as_deref(), Some("2.0"));
+        assert_eq!(doc.spine_ids, vec!["ch1"]);
+        assert_eq!(doc.manifest["ch1"].href, "Text/ch1.xhtml");
+    }
+
+    #[test]
+    fn validation_reports_missing_files_and_spine_items() {
+        let opf = OPF.replace(r#"<itemref idref="notes" linear="no"/>"#, r#"<itemref idref="notes" linear="no"/><itemref idref="fantasma"/>"#);
+        let bytes = build_epub(&[
+            (CONTAINER_PATH, CONTAINER),
+            ("OEBPS/content.opf", opf.as_str()),
+            ("OEBPS/nav.xhtml", NAV),
+            ("OEBPS/Text/ch1.xhtml", "<html><body><p>Hola</p></body></html>"),
+        ]);
+        let doc = EpubDocument::from_reader(Cursor::new(bytes)).unwrap();
+        assert_eq!(doc.validation_errors(), vec![
+            "El item 'fantasma' del spine no está en el manifiesto".to_string(),
+            "El archivo 'OEBPS/Text/notes.xhtml' del manifiesto (id 'notes') no está en el ZIP".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn collects_parse_warnings_instead_of_printing() {
+        let container = CONTAINER.replace("application/oebps-package+xml", "text/xml");
+        let opf = OPF.replace(r#"properties="nav""#, "");
+        let bytes = build_epub(&[
+            (CONTAINER_PATH, container.as_str()),
+            ("OEBPS/content.opf", opf.as_str()),
+            ("OEBPS/Text/ch1.xhtml", "<html><body><p>Hola</p></body></html>"),
+        ]);
+        let doc = EpubDocument::from_reader(Cursor::new(bytes)).unwrap();
+        assert_eq!(doc.toc_source, TocSource::Spine);
+        assert_eq!(doc.warnings.len(), 3);
+        assert!(doc.warnings[0].contains("media-type"));
+        assert!(doc.warnings[1].contains("tabla de contenidos"));
+        assert!(doc.warnings[2].contains("spine"));
+    }
+
+    #[test]
+    fn synthesizes_toc_from_linear_spine_items() {
+        let opf = OPF
+            .replace(r#"properties="nav""#, "")
+            .replace(r#"<itemref idref="notes" linear="no"/>"#,
+                r#"<itemref idref="notes" linear="no"/><itemref idref="ch2"/>"#)
+            .replace("</manifest>",
+                r#"<item id="ch2" href="Text/ch2.xhtml" media-type="application/xhtml+xml"/></manifest>"#);
+        let bytes = build_epub(&[
+            (CONTAINER_PATH, CONTAINER),
+            ("OEBPS/content.opf", opf.as_str()),
+            ("OEBPS/Text/ch1.xhtml", "<html><head><title>El comienzo</title></head><body><p>Hola</p></body></html>"),
+            ("OEBPS/Text/ch2.xhtml", "<html><body><p>Adiós</p></body></html>"),
+        ]);
+        let doc = EpubDocument::from_reader(Cursor::new(bytes)).unwrap();
+        assert_eq!(doc.toc_source, TocSource::Spine);
+        let hrefs: Vec<_> = doc.toc.iter().map(|entry| entry.href.as_str()).collect();
+        assert_eq!(hrefs, vec!["OEBPS/Text/ch1.xhtml", "OEBPS/Text/ch2.xhtml"]);
+        // Sin leer los capítulos: las etiquetas las pone la UI
+        assert!(doc.toc.iter().all(|entry| entry.label.is_empty()));
+    }
+
+    #[test]
+    fn from_reader_without_container_fails() {
+        let bytes = build_epub(&[("OEBPS/content.opf", OPF)]);
+        let result = EpubDocument::from_reader(Cursor::new(bytes));
+        assert!(matches!(result, Err(EpubError::MissingContainerXml)));
+    }
+
+    #[test]
+    fn parse_manifest_and_spine() {
+        let doc = Document::parse(OPF).unwrap();
+        let package = doc.root_element();
+        let manifest_node = package.children().find(|n| n.tag_name().name() == "manifest").unwrap();
+        let spine_node = package.children().find(|n| n.tag_name().name() == "spine").unwrap();
+
+        let manifest = parse_manifest(manifest_node).unwrap();
+        assert_eq!(manifest.len(), 3);
+        assert_eq!(manifest["ch1"].href, "Text/ch1.xhtml");
+        assert_eq!(manifest["nav"].properties.as_deref(), Some("nav"));
+
+        let spine = parse_spine(spine_node).unwrap();
+        assert_eq!(spine, vec![("ch1".to_string(), true), ("notes".to_string(), false)]);
+    }
+
+    #[test]
+    fn nav_and_ncx_build_toc_trees() {
+        let nav = r#"<html><body><nav epub:type="toc"><ol>
+  <li><a href="Text/ch1.xhtml">Uno</a><ol>
+    <li><a href="Text/ch1.xhtml#a">Uno.a</a><ol><li><a href="Text/ch1.xhtml#i">Uno.a.i</a></li></ol></li>
+    <li><a href="Text/ch1.xhtml#b">Uno.b</a></li>
+  </ol></li>
+  <li><a href="Text/ch2.xhtml">Dos</a></li>
+</ol></nav></body></html>"#;
+        let toc = parse_nav_xhtml(nav, "OEBPS/nav.xhtml").unwrap().toc;
+        let labels = |entries: &[TocEntry]| entries.iter().map(|entry| entry.label.clone()).collect::<Vec<_>>();
+        assert_eq!(labels(&toc), ["Uno", "Dos"]);
+        assert_eq!(labels(&toc[0].children), ["Uno.a", "Uno.b"]);
+        assert_eq!(labels(&toc[0].children[0].children), ["Uno.a.i"]);
+        assert_eq!(toc[0].children[0].children[0].depth, 3);
+
+        let ncx = r#"<ncx><navMap>
+  <navPoint id="n1"><navLabel><text>Uno</text></navLabel><content src="Text/ch1.xhtml"/>
+    <navPoint id="n2"><navLabel><text>Uno.a</text></navLabel><content src="Text/ch1.xhtml#a"/></navPoint>
+  </navPoint>
+  <navPoint id="n3"><navLabel><text>Dos</text></navLabel><content src="Text/ch2.xhtml"/></navPoint>
+</navMap></ncx>"#;
+        let toc = parse_ncx(ncx, "OEBPS/toc.ncx").unwrap();
+        assert_eq!(labels(&toc), ["Uno", "Dos"]);
+        assert_eq!(labels(&toc[0].children), ["Uno.a"]);
+        assert_eq!(labels(&crate::navigation::flatten_toc(&toc)), ["Uno", "Uno.a", "Dos"]);
+    }
+
+    #[test]
+    fn parse_nav_xhtml_reads_page_list() {
+        let nav = r#"<html><body>
+<nav epub:type="toc"><ol><li><a href="Text/ch1.xhtml">Uno</a></li></ol></nav>
+<nav epub:type="page-list"><ol>
+  <li><a href="Text/ch1.xhtml#p1">1</a></li>
+  <li><a href="Text/ch2.xhtml#p2">2</a></li>
+</ol></nav>
+</body></html>"#;
+        let NavContent { toc, page_list: pages, .. } = parse_nav_xhtml(nav, "OEBPS/nav.xhtml").unwrap();
+        assert_eq!(toc.len(), 1);
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[1].label, "2");
+        assert_eq!(pages[1].href, "OEBPS/Text/ch2.xhtml");
+        assert_eq!(pages[1].fragment.as_deref(), Some("p2"));
+    }
+
+    #[test]
+    fn parse_nav_xhtml_reads_landmarks() {
+        let nav = r#"<html><body>
+<nav epub:type="toc"><ol><li><a href="Text/ch1.xhtml">Uno</a></li></ol></nav>
+<nav epub:type="landmarks"><ol>
+  <li><a epub:type="toc" href="nav.xhtml">Índice</a></li>
+  <li><a epub:type="bodymatter" href="Text/ch1.xhtml#inicio">Comienzo</a></li>
+</ol></nav>
+</body></html>"#;
+        let landmarks = parse_nav_xhtml(nav, "OEBPS/nav.xhtml").unwrap().landmarks;
+        assert_eq!(landmarks.len(), 2);
+        assert_eq!(landmarks[1].kind, "bodymatter");
+        assert_eq!(landmarks[1].href, "OEBPS/Text/ch1.xhtml");
+        assert_eq!(landmarks[1].fragment.as_deref(), Some("inicio"));
+    }
+
+    #[test]
+    fn parse_guide_maps_text_to_bodymatter() {
+        let opf = r#"<guide><reference type="cover" title="Cubierta" href="cover.xhtml"/><reference type="text" href="Text/ch1.xhtml"/></guide>"#;
+        let doc = Document::parse(opf).unwrap();
+        let landmarks = parse_guide(doc.root_element(), "OEBPS/content.opf");
+        assert_eq!(landmarks.len(), 2);
+        assert_eq!(landmarks[0].label, "Cubierta");
+        assert_eq!(landmarks[1].kind, "bodymatter");
+        assert_eq!(landmarks[1].href, "OEBPS/Text/ch1.xhtml");
+    }
+
+    #[test]
+    fn metadata_parses_description_and_subjects() {
+        let opf = r#"<metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+  <dc:description>  &lt;p&gt;Una novela.&lt;/p&gt;  </dc:description>
+  <dc:subject>Ficción</dc:subject>
+  <dc:subject> </dc:subject>
+  <dc:subject>Aventuras</dc:subject>
+</metadata>"#;
+        let doc = Document::parse(opf).unwrap();
+        let metadata = Metadata::parse(doc.root_element()).unwrap();
+        assert_eq!(metadata.description.as_deref(), Some("<p>Una novela.</p>"));
+        assert_eq!(metadata.subjects, vec!["Ficción", "Aventuras"]);
+    }
+
+    #[test]
+    fn metadata_separates_publication_and_modification_dates() {
+        let epub3 = r#"<metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+  <dc:date>2001-05-01</dc:date>
+  <meta property="dcterms:modified">2020-01-02T10:00:00Z</meta>
+</metadata>"#;
+        let doc = Document::parse(epub3).unwrap();
+        let metadata = Metadata::parse(doc.root_element()).unwrap();
+        assert_eq!(metadata.date.as_deref(), Some("2001-05-01"));
+        assert_eq!(metadata.modified.as_deref(), Some("2020-01-02T10:00:00Z"));
+
+        let epub2 = r#"<metadata xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:opf="http://www.idpf.org/2007/opf">
+  <dc:date opf:event="modification">2019-03-03</dc:date>
+  <dc:date opf:event="publication">1999</dc:date>
+</metadata>"#;
+        let doc = Document::parse(epub2).unwrap();
+        let metadata = Metadata::parse(doc.root_element()).unwrap();
+        assert_eq!(metadata.date.as_deref(), Some("1999"));
+        assert_eq!(metadata.modified.as_deref(), Some("2019-03-03"));
+    }
+
+    #[test]
+    fn metadata_collects_refinements_by_id() {
+        let opf = r##"<metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+  <dc:title id="t1">Fundación</dc:title>
+  <meta refines="#t1" property="title-type">main</meta>
+  <dc:creator id="c1">Isaac Asimov</dc:creator>
+  <meta refines="#c1" property="role" scheme="marc:relators">aut</meta>
+  <meta refines="#c1" property="file-as">Asimov, Isaac</meta>
+  <meta property="belongs-to-collection" id="s1">Fundación</meta>
+  <meta refines="#s1" property="group-position">1</meta>
+</metadata>"##;
+        let doc = Document::parse(opf).unwrap();
+        let metadata = Metadata::parse(doc.root_element()).unwrap();
+        assert_eq!(metadata.refinements["c1"], vec![
+            ("role".to_string(), "aut".to_string()),
+            ("file-as".to_string(), "Asimov, Isaac".to_string()),
+        ]);
+        assert_eq!(metadata.refinement("t1", "title-type").as_deref(), Some("main"));
+        assert_eq!(metadata.creators[0].role.as_deref(), Some("aut"));
+        assert_eq!(metadata.series_index, Some(1.0));
     }
 
-    // El resto del código permanece igual
-    let prefix = if path_str.starts_with('/') { "/" } else { "" };
-    
-    if components.is_empty() {
-        prefix.to_string()
-    } else {
-        format!("{}{}", prefix, components.join("/"))
+    #[test]
+    fn parse_manifest_rejects_item_without_href() {
+        let doc = Document::parse(r#"<manifest><item id="x" media-type="text/css"/></manifest>"#).unwrap();
+        assert!(parse_manifest(doc.root_element()).is_err());
     }
 }
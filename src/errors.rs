@@ -48,6 +48,9 @@ pub enum EpubError {
     #[error("No se pudo leer el archivo de contenido: {0}")]
     ContentReadError(String),
 
+    #[error("El capítulo {0} no tiene texto")]
+    EmptyChapter(String),
+
     #[error("Índice de capítulo fuera de rango: {0}")]
     InvalidChapterIndex(usize),
 
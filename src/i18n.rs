@@ -0,0 +1,516 @@
+// src/i18n.rs
+// Textos de la interfaz en español (por defecto) o en inglés. El idioma se elige con
+// `language` en la configuración o, si no se indica, con las variables de locale
+use std::env;
+use std::fmt::{Display, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Lang {
+    #[default]
+    Es,
+    En,
+}
+
+// Mensajes traducibles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Msg {
+    And, // Conjunción de enumeraciones: "A, B y C"
+    NotAvailable,
+    Metadata,
+    Title,
+    Author,
+    Language,
+    Identifier,
+    Publisher,
+    Date,
+    PublicationDate,
+    Modified,
+    Series,
+    EpubVersion,
+    ScreensRead,
+    Subjects,
+    Description,
+    Chapter,
+    Of, // "Capítulo 3 de 10"
+    LastChapter,
+    FirstChapter,
+    SearchAccept,
+    SearchBack,
+    NoMatches,
+    OneMatch,
+    Matches,
+    Match, // "Coincidencia 3 de 12"
+    NoMatchesFor,
+    // Mensajes con "{}" para sus datos (ver Lang::format)
+    ChapterSkipped,
+    NoReadableContent,
+    NoTextIn,
+    ChapterReadError,
+    ChapterLoadError,
+    ChapterPathError,
+    ChapterCounts,
+    InvalidChapter,
+    BookPercent,
+    NoImageOnLine,
+    ImagesDisabled,
+    ImageReadError,
+    CloseWithEsc,
+    UnsupportedImage,
+    ZenMode,
+    AutoScrollOn,
+    AutoScrollPaused,
+    AutoScrollSpeed,
+    AutoScrollStopped,
+    AutoScrollProgress,
+    InvalidPercent,
+    InvalidChapterNumber,
+    GotoLabelUsage,
+    CurrentChapterId,
+    NoChapterId,
+    OpenIdUsage,
+    InvalidTocDepth,
+    NoLandmarks,
+    NoCover,
+    TextStyleSet,
+    ParagraphsSet,
+    UnknownParagraphStyle,
+    HeadingsSet,
+    UnknownHeadings,
+    UnknownStyle,
+    OpenUsage,
+    ExportTocUsage,
+    ChapterCopied,
+    LineCopied,
+    NoLineToCopy,
+    CurrentTheme,
+    ThemeSet,
+    UnknownTheme,
+    FullWidth,
+    WidthSet,
+    InvalidWidth,
+    WpmSet,
+    InvalidWpm,
+    ScrollStepSet,
+    InvalidScrollStep,
+    AutoAdvanceOn,
+    AutoAdvanceOff,
+    UnknownCommand,
+    OpenFailed,
+    NoChapters,
+    Warning,
+    WarningAndMore,
+    SavePositionFailed,
+    ChapterExported,
+    ExportFileName, // Nombre de archivo de exportación si el libro no tiene título
+    WriteFailed,
+    NoToc,
+    JsonFailed,
+    TocExported,
+    NoPageList,
+    PageFound,
+    PageNotFound,
+    NoLinkOnLine,
+    ExternalLink,
+    LinkOutsideBook,
+    FootnoteOpened,
+    LinkFollowed,
+    AnchorNotFound,
+    NoPreviousPositions,
+    NoMoreSections,
+    Section,
+    SectionOfMatches,
+    NoTocEntryContains,
+    IdNotInSpine,
+    IdNotInManifest,
+    SkippedOnePage,
+    SkippedPages,
+    BackToStart,
+    TocEntryWithoutChapter,
+    PickerHelp,
+    FileMissing,
+    RecentBooks,
+    NoRecentBooks,
+    RecentChapter,
+    Note,
+    Image,
+    TableOfContents,
+    Landmarks,
+    OpenWith,
+    EpubInfo,
+    OpfFile,
+    RootDirectory,
+    ZipRoot,
+    ManifestItems,
+    SpineItems,
+    TocLine,
+    TocFromSpine,
+    TocNone,
+    NoWarnings,
+    Warnings,
+    Chapters,
+    WordsAndMinutes,
+    Unreadable,
+    ChapterPercent,
+    ChapterEnd,
+}
+
+impl Lang {
+    // Idioma por nombre ("es", "en", "español", "english"...)
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "es" | "español" | "spanish" => Some(Lang::Es),
+            "en" | "english" | "inglés" => Some(Lang::En),
+            _ => None,
+        }
+    }
+
+    // Idioma de un locale POSIX ("en_US.UTF-8", "es_ES"...). Solo se reconoce el inglés:
+    // cualquier otro deja el español por defecto
+    pub fn from_locale(locale: &str) -> Self {
+        let primary = locale.split(['_', '-', '.', '@']).next().unwrap_or("");
+        match primary.eq_ignore_ascii_case("en") {
+            true => Lang::En,
+            false => Lang::Es,
+        }
+    }
+
+    // Idioma configurado o, si está vacío o no se reconoce, el del entorno
+    // (LC_ALL, LC_MESSAGES y LANG, en el orden en que los consulta POSIX)
+    pub fn detect(configured: &str) -> Self {
+        Lang::by_name(configured).unwrap_or_else(|| {
+            ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+                .filter_map(|name| env::var(name).ok())
+                .find(|value| !value.is_empty())
+                .map(|locale| Lang::from_locale(&locale))
+                .unwrap_or_default()
+        })
+    }
+
+    pub fn text(self, message: Msg) -> &'static str {
+        match self {
+            Lang::Es => spanish(message),
+            Lang::En => english(message),
+        }
+    }
+
+    // Texto de un mensaje con cada "{}" sustituido, en orden, por uno de los argumentos
+    pub fn format(self, message: Msg, args: &[&dyn Display]) -> String {
+        let mut text = String::new();
+        let mut args = args.iter();
+        for (i, piece) in self.text(message).split("{}").enumerate() {
+            if i > 0 {
+                if let Some(arg) = args.next() {
+                    let _ = write!(text, "{}", arg);
+                }
+            }
+            text.push_str(piece);
+        }
+        text
+    }
+}
+
+fn spanish(message: Msg) -> &'static str {
+    match message {
+        Msg::And => "y",
+        Msg::NotAvailable => "N/A",
+        Msg::Metadata => "Metadatos",
+        Msg::Title => "Título",
+        Msg::Author => "Autor",
+        Msg::Language => "Idioma",
+        Msg::Identifier => "Identificador",
+        Msg::Publisher => "Editor",
+        Msg::Date => "Fecha",
+        Msg::PublicationDate => "Fecha de publicación",
+        Msg::Modified => "Modificado",
+        Msg::Series => "Serie",
+        Msg::EpubVersion => "Versión EPUB",
+        Msg::ScreensRead => "Pantallas leídas en esta sesión",
+        Msg::Subjects => "Materias",
+        Msg::Description => "Descripción",
+        Msg::Chapter => "Capítulo",
+        Msg::Of => "de",
+        Msg::LastChapter => "Ya estás en el último capítulo",
+        Msg::FirstChapter => "Ya estás en el primer capítulo",
+        Msg::SearchAccept => "Enter: aceptar",
+        Msg::SearchBack => "Esc: volver",
+        Msg::NoMatches => "sin coincidencias",
+        Msg::OneMatch => "coincidencia",
+        Msg::Matches => "coincidencias",
+        Msg::Match => "Coincidencia",
+        Msg::NoMatchesFor => "Sin coincidencias para",
+        Msg::ChapterSkipped => "{} — {}, se ha saltado",
+        Msg::NoReadableContent => "Este capítulo no tiene contenido legible",
+        Msg::NoTextIn => "{} (sin texto en {})",
+        Msg::ChapterReadError => "No se pudo leer el capítulo: {}",
+        Msg::ChapterLoadError => "Error al cargar el capítulo {}. Pulsa Enter para saltar al siguiente",
+        Msg::ChapterPathError => "Error al obtener la ruta del capítulo",
+        Msg::ChapterCounts => "Capítulo {}: {} líneas, {} palabras — línea {} de {}",
+        Msg::InvalidChapter => "Capítulo {} no válido",
+        Msg::BookPercent => "{} ({}% del libro)",
+        Msg::NoImageOnLine => "No hay ninguna imagen en la línea resaltada",
+        Msg::ImagesDisabled => "Las imágenes están desactivadas (images = true en config.toml)",
+        Msg::ImageReadError => "No se pudo leer la imagen: {}",
+        Msg::CloseWithEsc => "{} (Esc para cerrar)",
+        Msg::UnsupportedImage => "Formato de imagen no admitido: {}",
+        Msg::ZenMode => "Modo zen (z o :zen para salir)",
+        Msg::AutoScrollOn => "Auto-scroll: {} ppm (s para pausar, +/- para cambiar)",
+        Msg::AutoScrollPaused => "Auto-scroll en pausa",
+        Msg::AutoScrollSpeed => "Auto-scroll: {} ppm",
+        Msg::AutoScrollStopped => "Auto-scroll detenido: fin del capítulo",
+        Msg::AutoScrollProgress => "[auto {} ppm] {}",
+        Msg::InvalidPercent => "Porcentaje inválido: {}",
+        Msg::InvalidChapterNumber => "Número de capítulo inválido: {}",
+        Msg::GotoLabelUsage => "Uso: goto-label <texto>",
+        Msg::CurrentChapterId => "Id del capítulo actual en el manifiesto: {}",
+        Msg::NoChapterId => "El capítulo actual no tiene id",
+        Msg::OpenIdUsage => "Uso: open-id [id del manifiesto]",
+        Msg::InvalidTocDepth => "Profundidad de TOC inválida: {}",
+        Msg::NoLandmarks => "Este libro no define puntos de referencia",
+        Msg::NoCover => "Este libro no declara una imagen de portada",
+        Msg::TextStyleSet => "Estilo de texto: {}",
+        Msg::ParagraphsSet => "Párrafos: {}",
+        Msg::UnknownParagraphStyle => "Estilo de párrafo desconocido: {} (usa indent o spaced)",
+        Msg::HeadingsSet => "Encabezados: {}",
+        Msg::UnknownHeadings => "Modo de encabezados desconocido: {} (usa numbered o plain)",
+        Msg::UnknownStyle => "Estilo desconocido: {} (usa rich, plain o markdown)",
+        Msg::OpenUsage => "Uso: open <ruta_al_archivo.epub>",
+        Msg::ExportTocUsage => "Uso: export-toc [--json] <ruta>",
+        Msg::ChapterCopied => "Capítulo copiado al portapapeles",
+        Msg::LineCopied => "Línea copiada al portapapeles",
+        Msg::NoLineToCopy => "No hay ninguna línea que copiar",
+        Msg::CurrentTheme => "Tema actual: {}",
+        Msg::ThemeSet => "Tema: {}",
+        Msg::UnknownTheme => "Tema desconocido: {} (usa dark o light)",
+        Msg::FullWidth => "Ancho de lectura: completo",
+        Msg::WidthSet => "Ancho de lectura: {} columnas",
+        Msg::InvalidWidth => "Ancho inválido: {}",
+        Msg::WpmSet => "Velocidad de lectura: {} ppm",
+        Msg::InvalidWpm => "Velocidad inválida: {} (entre {} y {} palabras por minuto)",
+        Msg::ScrollStepSet => "j/k avanzan {} líneas",
+        Msg::InvalidScrollStep => "Paso de desplazamiento inválido: {}",
+        Msg::AutoAdvanceOn => "Avance automático de capítulo activado",
+        Msg::AutoAdvanceOff => "Avance automático de capítulo desactivado",
+        Msg::UnknownCommand => "Comando desconocido: {}",
+        Msg::OpenFailed => "No se pudo abrir '{}': {}",
+        Msg::NoChapters => "'{}' no contiene capítulos",
+        Msg::Warning => "Advertencia: {}",
+        Msg::WarningAndMore => "Advertencia: {} (y {} más)",
+        Msg::SavePositionFailed => "No se pudo guardar la posición de lectura: {}. Pulsa q otra vez (o :q!) para salir sin guardarla",
+        Msg::ChapterExported => "Capítulo {} exportado a {}",
+        Msg::ExportFileName => "capitulo",
+        Msg::WriteFailed => "No se pudo escribir {}: {}",
+        Msg::NoToc => "El libro no tiene tabla de contenidos",
+        Msg::JsonFailed => "No se pudo generar el JSON: {}",
+        Msg::TocExported => "Tabla de contenidos exportada a {}",
+        Msg::NoPageList => "Este libro no incluye una lista de páginas",
+        Msg::PageFound => "Página {} — {}",
+        Msg::PageNotFound => "Página no encontrada: {}",
+        Msg::NoLinkOnLine => "No hay ningún enlace en la línea resaltada",
+        Msg::ExternalLink => "Enlace externo: {}",
+        Msg::LinkOutsideBook => "El destino del enlace no está en el libro: {}",
+        Msg::FootnoteOpened => "Nota al pie (Esc para cerrar)",
+        Msg::LinkFollowed => "Enlace seguido (Ctrl-o para volver)",
+        Msg::AnchorNotFound => "No se encontró el ancla de {} (Ctrl-o para volver)",
+        Msg::NoPreviousPositions => "No hay posiciones anteriores",
+        Msg::NoMoreSections => "No hay más secciones",
+        Msg::Section => "Sección: {}",
+        Msg::SectionOfMatches => "Sección: {} (primera de {} coincidencias)",
+        Msg::NoTocEntryContains => "Ninguna entrada de la TOC contiene \"{}\"",
+        Msg::IdNotInSpine => "El item '{}' está en el manifiesto pero no en el spine",
+        Msg::IdNotInManifest => "No hay ningún item con id '{}' en el manifiesto",
+        Msg::SkippedOnePage => "se ha saltado 1 página inicial casi sin texto",
+        Msg::SkippedPages => "se han saltado {} páginas iniciales casi sin texto",
+        Msg::BackToStart => "{} — {} (:goto 1 para volver al principio)",
+        Msg::TocEntryWithoutChapter => "Esta entrada no lleva a ningún capítulo del libro",
+        Msg::PickerHelp => "j/k o flechas para elegir, Enter para abrir, q para salir",
+        Msg::FileMissing => "El archivo ya no existe: {}",
+        Msg::RecentBooks => "EPUB Reader - Libros recientes",
+        Msg::NoRecentBooks => "No hay libros recientes. Uso: epub_reader <ruta_al_archivo.epub>",
+        Msg::RecentChapter => "(cap. {})",
+        Msg::Note => "Nota",
+        Msg::Image => "Imagen",
+        Msg::TableOfContents => "Tabla de Contenidos",
+        Msg::Landmarks => "Puntos de referencia",
+        Msg::OpenWith => "Ir con: goto {}",
+        Msg::EpubInfo => "Información del EPUB",
+        Msg::OpfFile => "Archivo OPF: {}",
+        Msg::RootDirectory => "Directorio raíz: {}",
+        Msg::ZipRoot => "(raíz del ZIP)",
+        Msg::ManifestItems => "Elementos del manifiesto: {}",
+        Msg::SpineItems => "Items del spine: {} ({} lineales)",
+        Msg::TocLine => "Tabla de contenidos: {} ({} entradas)",
+        Msg::TocFromSpine => "generada a partir del spine (el libro no trae ninguna)",
+        Msg::TocNone => "ninguna",
+        Msg::NoWarnings => "Sin advertencias",
+        Msg::Warnings => "Advertencias",
+        Msg::Chapters => "Capítulos",
+        Msg::WordsAndMinutes => "{} palabras {} min",
+        Msg::Unreadable => "ilegible",
+        Msg::ChapterPercent => "[cap {}%]",
+        Msg::ChapterEnd => "[cap FIN]",
+    }
+}
+
+fn english(message: Msg) -> &'static str {
+    match message {
+        Msg::And => "and",
+        Msg::NotAvailable => "N/A",
+        Msg::Metadata => "Metadata",
+        Msg::Title => "Title",
+        Msg::Author => "Author",
+        Msg::Language => "Language",
+        Msg::Identifier => "Identifier",
+        Msg::Publisher => "Publisher",
+        Msg::Date => "Date",
+        Msg::PublicationDate => "Publication date",
+        Msg::Modified => "Modified",
+        Msg::Series => "Series",
+        Msg::EpubVersion => "EPUB version",
+        Msg::ScreensRead => "Screens read this session",
+        Msg::Subjects => "Subjects",
+        Msg::Description => "Description",
+        Msg::Chapter => "Chapter",
+        Msg::Of => "of",
+        Msg::LastChapter => "Already at the last chapter",
+        Msg::FirstChapter => "Already at the first chapter",
+        Msg::SearchAccept => "Enter: accept",
+        Msg::SearchBack => "Esc: back",
+        Msg::NoMatches => "no matches",
+        Msg::OneMatch => "match",
+        Msg::Matches => "matches",
+        Msg::Match => "Match",
+        Msg::NoMatchesFor => "No matches for",
+        Msg::ChapterSkipped => "{} — {}, skipped",
+        Msg::NoReadableContent => "This chapter has no readable content",
+        Msg::NoTextIn => "{} (no text in {})",
+        Msg::ChapterReadError => "Could not read the chapter: {}",
+        Msg::ChapterLoadError => "Error loading chapter {}. Press Enter to skip to the next one",
+        Msg::ChapterPathError => "Could not get the chapter's path",
+        Msg::ChapterCounts => "Chapter {}: {} lines, {} words — line {} of {}",
+        Msg::InvalidChapter => "Invalid chapter {}",
+        Msg::BookPercent => "{} ({}% of the book)",
+        Msg::NoImageOnLine => "There is no image on the highlighted line",
+        Msg::ImagesDisabled => "Images are disabled (images = true in config.toml)",
+        Msg::ImageReadError => "Could not read the image: {}",
+        Msg::CloseWithEsc => "{} (Esc to close)",
+        Msg::UnsupportedImage => "Unsupported image format: {}",
+        Msg::ZenMode => "Zen mode (z or :zen to leave)",
+        Msg::AutoScrollOn => "Auto-scroll: {} wpm (s to pause, +/- to change)",
+        Msg::AutoScrollPaused => "Auto-scroll paused",
+        Msg::AutoScrollSpeed => "Auto-scroll: {} wpm",
+        Msg::AutoScrollStopped => "Auto-scroll stopped: end of the chapter",
+        Msg::AutoScrollProgress => "[auto {} wpm] {}",
+        Msg::InvalidPercent => "Invalid percentage: {}",
+        Msg::InvalidChapterNumber => "Invalid chapter number: {}",
+        Msg::GotoLabelUsage => "Usage: goto-label <text>",
+        Msg::CurrentChapterId => "Manifest id of the current chapter: {}",
+        Msg::NoChapterId => "The current chapter has no id",
+        Msg::OpenIdUsage => "Usage: open-id [manifest id]",
+        Msg::InvalidTocDepth => "Invalid TOC depth: {}",
+        Msg::NoLandmarks => "This book defines no landmarks",
+        Msg::NoCover => "This book declares no cover image",
+        Msg::TextStyleSet => "Text style: {}",
+        Msg::ParagraphsSet => "Paragraphs: {}",
+        Msg::UnknownParagraphStyle => "Unknown paragraph style: {} (use indent or spaced)",
+        Msg::HeadingsSet => "Headings: {}",
+        Msg::UnknownHeadings => "Unknown headings mode: {} (use numbered or plain)",
+        Msg::UnknownStyle => "Unknown style: {} (use rich, plain or markdown)",
+        Msg::OpenUsage => "Usage: open <path_to_file.epub>",
+        Msg::ExportTocUsage => "Usage: export-toc [--json] <path>",
+        Msg::ChapterCopied => "Chapter copied to the clipboard",
+        Msg::LineCopied => "Line copied to the clipboard",
+        Msg::NoLineToCopy => "There is no line to copy",
+        Msg::CurrentTheme => "Current theme: {}",
+        Msg::ThemeSet => "Theme: {}",
+        Msg::UnknownTheme => "Unknown theme: {} (use dark or light)",
+        Msg::FullWidth => "Reading width: full",
+        Msg::WidthSet => "Reading width: {} columns",
+        Msg::InvalidWidth => "Invalid width: {}",
+        Msg::WpmSet => "Reading speed: {} wpm",
+        Msg::InvalidWpm => "Invalid speed: {} (between {} and {} words per minute)",
+        Msg::ScrollStepSet => "j/k move {} lines",
+        Msg::InvalidScrollStep => "Invalid scroll step: {}",
+        Msg::AutoAdvanceOn => "Automatic chapter advance on",
+        Msg::AutoAdvanceOff => "Automatic chapter advance off",
+        Msg::UnknownCommand => "Unknown command: {}",
+        Msg::OpenFailed => "Could not open '{}': {}",
+        Msg::NoChapters => "'{}' contains no chapters",
+        Msg::Warning => "Warning: {}",
+        Msg::WarningAndMore => "Warning: {} (and {} more)",
+        Msg::SavePositionFailed => "Could not save the reading position: {}. Press q again (or :q!) to quit without saving it",
+        Msg::ChapterExported => "Chapter {} exported to {}",
+        Msg::ExportFileName => "chapter",
+        Msg::WriteFailed => "Could not write {}: {}",
+        Msg::NoToc => "The book has no table of contents",
+        Msg::JsonFailed => "Could not generate the JSON: {}",
+        Msg::TocExported => "Table of contents exported to {}",
+        Msg::NoPageList => "This book has no page list",
+        Msg::PageFound => "Page {} — {}",
+        Msg::PageNotFound => "Page not found: {}",
+        Msg::NoLinkOnLine => "There is no link on the highlighted line",
+        Msg::ExternalLink => "External link: {}",
+        Msg::LinkOutsideBook => "The link's target is not in the book: {}",
+        Msg::FootnoteOpened => "Footnote (Esc to close)",
+        Msg::LinkFollowed => "Link followed (Ctrl-o to go back)",
+        Msg::AnchorNotFound => "Anchor of {} not found (Ctrl-o to go back)",
+        Msg::NoPreviousPositions => "There are no previous positions",
+        Msg::NoMoreSections => "There are no more sections",
+        Msg::Section => "Section: {}",
+        Msg::SectionOfMatches => "Section: {} (first of {} matches)",
+        Msg::NoTocEntryContains => "No TOC entry contains \"{}\"",
+        Msg::IdNotInSpine => "The item '{}' is in the manifest but not in the spine",
+        Msg::IdNotInManifest => "There is no item with id '{}' in the manifest",
+        Msg::SkippedOnePage => "skipped 1 opening page with almost no text",
+        Msg::SkippedPages => "skipped {} opening pages with almost no text",
+        Msg::BackToStart => "{} — {} (:goto 1 to go back to the beginning)",
+        Msg::TocEntryWithoutChapter => "This entry does not lead to any chapter of the book",
+        Msg::PickerHelp => "j/k or arrows to choose, Enter to open, q to quit",
+        Msg::FileMissing => "The file no longer exists: {}",
+        Msg::RecentBooks => "EPUB Reader - Recent books",
+        Msg::NoRecentBooks => "No recent books. Usage: epub_reader <path_to_file.epub>",
+        Msg::RecentChapter => "(ch. {})",
+        Msg::Note => "Note",
+        Msg::Image => "Image",
+        Msg::TableOfContents => "Table of Contents",
+        Msg::Landmarks => "Landmarks",
+        Msg::OpenWith => "Open with: goto {}",
+        Msg::EpubInfo => "EPUB information",
+        Msg::OpfFile => "OPF file: {}",
+        Msg::RootDirectory => "Root directory: {}",
+        Msg::ZipRoot => "(ZIP root)",
+        Msg::ManifestItems => "Manifest items: {}",
+        Msg::SpineItems => "Spine items: {} ({} linear)",
+        Msg::TocLine => "Table of contents: {} ({} entries)",
+        Msg::TocFromSpine => "generated from the spine (the book has none)",
+        Msg::TocNone => "none",
+        Msg::NoWarnings => "No warnings",
+        Msg::Warnings => "Warnings",
+        Msg::Chapters => "Chapters",
+        Msg::WordsAndMinutes => "{} words {} min",
+        Msg::Unreadable => "unreadable",
+        Msg::ChapterPercent => "[ch {}%]",
+        Msg::ChapterEnd => "[ch BOT]",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_language_from_config_or_locale() {
+        assert_eq!(Lang::by_name("EN"), Some(Lang::En));
+        assert_eq!(Lang::by_name("fr"), None);
+        assert_eq!(Lang::from_locale("en_US.UTF-8"), Lang::En);
+        assert_eq!(Lang::from_locale("fr_FR.UTF-8"), Lang::Es);
+        assert_eq!(Lang::detect("es"), Lang::Es);
+        assert_eq!(Lang::En.text(Msg::PublicationDate), "Publication date");
+    }
+
+    #[test]
+    fn formats_messages_with_arguments() {
+        assert_eq!(Lang::Es.format(Msg::ChapterExported, &[&3, &"c.txt"]), "Capítulo 3 exportado a c.txt");
+        assert_eq!(Lang::En.format(Msg::ChapterExported, &[&3, &"c.txt"]), "Chapter 3 exported to c.txt");
+        assert_eq!(Lang::En.format(Msg::NoToc, &[]), "The book has no table of contents");
+    }
+}
@@ -1,6 +1,7 @@
 // src/main.rs
 use std::env;
-use std::path::Path;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
 use std::process;
 
 // Define los módulos localmente
@@ -9,32 +10,161 @@ mod render;
 mod navigation;
 mod metadata;
 mod errors;
+mod cli;
+mod path_utils;
+mod clipboard;
+mod config;
+mod recent;
+mod i18n;
 mod ui;
 
+use cli::CliArgs;
+use config::AppConfig;
 use epub::EpubDocument;
+use render::{CaptionLabels, QuoteStyle, RenderOptions};
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        eprintln!("Uso: {} <ruta_al_archivo.epub>", args[0]);
+    let mut args = env::args();
+    let program = args.next().unwrap_or_else(|| "epub_reader".to_string());
+    let cli = match CliArgs::parse(args) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            eprintln!("{}", cli::usage(&program));
+            process::exit(1);
+        }
+    };
+
+    // Validación: se informa de los problemas en vez de terminar en el primero
+    if let (Some(path), true) = (cli.path.as_deref(), cli.validate) {
+        let valid = validate(path, cli.reads_stdin());
+        process::exit(if valid { 0 } else { 1 });
+    }
+
+    // Modos no interactivos: imprimir un capítulo o el libro entero y salir
+    if let (Some(path), true) = (cli.path.as_deref(), cli.is_batch()) {
+        let epub_doc = if cli.reads_stdin() { open_stdin_or_exit() } else { open_epub_or_exit(path) };
+        // En la interfaz las advertencias se ven con :info; aquí van a stderr, sin mezclarse con el texto
+        for warning in &epub_doc.warnings {
+            eprintln!("Advertencia: {}", warning);
+        }
+        let result = match cli.chapter {
+            Some(chapter) => dump_chapter(epub_doc, chapter, cli.json),
+            None => dump_text(epub_doc),
+        };
+        if let Err(e) = result {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    // Sin argumentos se muestra el selector de libros recientes
+    let book = cli.path.as_deref().map(|path| (open_epub_or_exit(path), PathBuf::from(path)));
+
+    // Comprobar --start antes de entrar en la interfaz para poder mostrar el error
+    if let (Some(start), Some((epub_doc, _))) = (cli.start, &book) {
+        let total = epub_doc.create_navigator().total_chapters();
+        if start > total {
+            eprintln!("Error: El capítulo {} no existe (el libro tiene {})", start, total);
+            process::exit(1);
+        }
+    }
+
+    // Una configuración inválida no impide leer: se avisa y se usan los valores por defecto
+    let config = AppConfig::load().unwrap_or_else(|e| {
+        eprintln!("Advertencia: {}", e);
+        AppConfig::default()
+    });
+
+    // Iniciar la interfaz de usuario con ratatui
+    if let Err(e) = ui::start_ui(book, &config, cli.start, !cli.no_resume) {
+        eprintln!("Error al iniciar la interfaz de usuario: {}", e);
+        process::exit(1);
+    }
+}
+
+// Comprueba la estructura del EPUB e imprime un problema por línea ("ERROR: ..." o
+// "ADVERTENCIA: ...") seguido de un resumen. Devuelve false si hay algún error
+fn validate(path: &str, from_stdin: bool) -> bool {
+    let opened = if from_stdin {
+        let mut bytes = Vec::new();
+        io::stdin().read_to_end(&mut bytes)
+            .map_err(|e| e.to_string())
+            .and_then(|_| EpubDocument::from_bytes(bytes).map_err(|e| e.to_string()))
+    } else {
+        EpubDocument::open(Path::new(path)).map_err(|e| e.to_string())
+    };
+    let epub_doc = match opened {
+        Ok(doc) => doc,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            println!("{}: no válido (el EPUB no se pudo parsear)", path);
+            return false;
+        }
+    };
+
+    let errors = epub_doc.validation_errors();
+    for error in &errors {
+        println!("ERROR: {}", error);
+    }
+    for warning in &epub_doc.warnings {
+        println!("ADVERTENCIA: {}", warning);
+    }
+    let verdict = if errors.is_empty() { "válido" } else { "no válido" };
+    println!("{}: {} ({} errores, {} advertencias)", path, verdict, errors.len(), epub_doc.warnings.len());
+    errors.is_empty()
+}
+
+// Lee el EPUB completo de la entrada estándar y lo parsea en memoria
+fn open_stdin_or_exit() -> EpubDocument {
+    let mut bytes = Vec::new();
+    if let Err(e) = io::stdin().read_to_end(&mut bytes) {
+        eprintln!("Error al leer el EPUB de stdin: {}", e);
         process::exit(1);
     }
+    let epub_doc = match EpubDocument::from_bytes(bytes) {
+        Ok(doc) => doc,
+        Err(e) => {
+            eprintln!("Error al parsear el EPUB recibido por stdin: {}", e);
+            process::exit(1);
+        }
+    };
+    ensure_readable_or_exit(epub_doc)
+}
 
-    let epub_path = Path::new(&args[1]);
-    if !epub_path.exists() || epub_path.extension().map_or(true, |ext| ext != "epub") {
-        eprintln!("Error: El archivo '{}' no existe o no es un archivo .epub", args[1]);
+// Valida la ruta y abre el EPUB, terminando el programa con un mensaje si falla
+fn open_epub_or_exit(path_str: &str) -> EpubDocument {
+    let epub_path = Path::new(path_str);
+    if !epub_path.exists() || epub_path.extension().is_none_or(|ext| ext != "epub") {
+        eprintln!("Error: El archivo '{}' no existe o no es un archivo .epub", path_str);
         process::exit(1);
     }
 
-    // Abrir y parsear el EPUB
-    let mut epub_doc = match EpubDocument::open(epub_path) {
+    // Abrir y parsear el EPUB, mostrando cada paso en una sola línea de stderr
+    // (solo en un terminal, para no ensuciar redirecciones como --chapter > archivo)
+    let show_progress = io::stderr().is_terminal();
+    let opened = EpubDocument::open_with_progress(epub_path, |step| {
+        if show_progress {
+            eprint!("\r\x1b[KAbriendo {}: {}...", path_str, step);
+        }
+    });
+    if show_progress {
+        eprint!("\r\x1b[K");
+    }
+    let epub_doc = match opened {
         Ok(doc) => doc,
         Err(e) => {
             eprintln!("Error al abrir o parsear el EPUB: {}", e);
             process::exit(1);
         }
     };
+    ensure_readable_or_exit(epub_doc)
+}
 
+// Termina el programa si el EPUB no tiene capítulos o no se puede leer ninguno, venga de
+// un archivo o de stdin
+fn ensure_readable_or_exit(mut epub_doc: EpubDocument) -> EpubDocument {
     // Verificar que el EPUB tenga capítulos
     let navigator = epub_doc.create_navigator();
     if navigator.total_chapters() == 0 {
@@ -42,9 +172,77 @@ fn main() {
         process::exit(1);
     }
 
-    // Iniciar la interfaz de usuario con ratatui
-    if let Err(e) = ui::start_ui(&mut epub_doc) {
-        eprintln!("Error al iniciar la interfaz de usuario: {}", e);
+    // Y que al menos uno se pueda leer: si fallan todos, la interfaz solo mostraría errores.
+    // El recorrido se detiene en el primer capítulo legible
+    let mut first_error = None;
+    let readable = epub_doc.chapters().any(|chapter| match chapter {
+        Ok(_) => true,
+        Err(e) => {
+            first_error.get_or_insert(e);
+            false
+        }
+    });
+    if !readable {
+        eprintln!("Error: No se pudo leer ningún capítulo del EPUB ({} en el spine)", navigator.total_chapters());
+        if let Some(e) = first_error {
+            eprintln!("Primer error: {}", e);
+        }
         process::exit(1);
     }
+
+    epub_doc
+}
+
+// Escribe en stdout el texto renderizado de un capítulo (basado en 1), o un objeto JSON
+// { "index", "href", "text" } si se pidió --json
+fn dump_chapter(mut epub_doc: EpubDocument, chapter: usize, json: bool) -> Result<(), String> {
+    let mut navigator = epub_doc.create_navigator();
+    if !navigator.goto(chapter) {
+        return Err(format!(
+            "El capítulo {} no existe (el libro tiene {})",
+            chapter,
+            navigator.total_chapters()
+        ));
+    }
+    let href = navigator.current_chapter_href().map_err(|e| e.to_string())?;
+    let content = epub_doc.read_chapter_content(&href).map_err(|e| e.to_string())?;
+    let options = RenderOptions {
+        quote_style: QuoteStyle::for_language(epub_doc.metadata.language.as_deref()),
+        caption_labels: CaptionLabels::for_language(epub_doc.metadata.language.as_deref()),
+        image_label: render::image_label_for_language(epub_doc.metadata.language.as_deref()),
+        ..RenderOptions::default()
+    };
+    let text = render::render_xhtml_to_text_with(&content, &options);
+
+    if json {
+        let value = serde_json::json!({ "index": chapter, "href": href, "text": text });
+        println!("{}", value);
+    } else {
+        println!("{}", text);
+    }
+    Ok(())
+}
+
+// Escribe en stdout el texto de todos los capítulos en el orden del spine, separados por
+// una línea en blanco. Los capítulos ilegibles se avisan por stderr y se saltan
+fn dump_text(mut epub_doc: EpubDocument) -> Result<(), String> {
+    let options = RenderOptions {
+        quote_style: QuoteStyle::for_language(epub_doc.metadata.language.as_deref()),
+        caption_labels: CaptionLabels::for_language(epub_doc.metadata.language.as_deref()),
+        image_label: render::image_label_for_language(epub_doc.metadata.language.as_deref()),
+        ..RenderOptions::default()
+    };
+    let mut stdout = io::stdout().lock();
+    for chapter in epub_doc.chapters() {
+        match chapter {
+            Ok((_, content)) => {
+                let text = render::render_xhtml_to_text_with(&content, &options);
+                if !text.is_empty() {
+                    writeln!(stdout, "{}\n", text).map_err(|e| e.to_string())?;
+                }
+            }
+            Err(e) => eprintln!("Advertencia: {}", e),
+        }
+    }
+    Ok(())
 }
@@ -1,48 +1,211 @@
 // src/metadata.rs
+use std::collections::HashMap;
 use roxmltree::Node;
 use crate::errors::EpubError;
+use crate::i18n::{Lang, Msg};
+
+// Un autor/colaborador (<dc:creator>) con su rol opcional (código MARC: aut, trl, ill...)
+#[derive(Debug, Clone)]
+pub struct Creator {
+    pub name: String,
+    pub role: Option<String>,
+}
 
 #[derive(Debug, Default)]
 pub struct Metadata {
     pub title: Option<String>,
-    pub creator: Option<String>,
+    pub creators: Vec<Creator>,
     pub language: Option<String>,
     pub identifier: Option<String>,
     pub publisher: Option<String>,
-    pub date: Option<String>,
-    // Puedes añadir más campos según necesites (subject, description, rights, etc.)
+    pub date: Option<String>,        // Fecha de publicación (<dc:date>)
+    pub modified: Option<String>,    // Última modificación (<meta property="dcterms:modified">)
+    pub series: Option<String>,
+    pub series_index: Option<f32>,
+    pub description: Option<String>, // <dc:description>; puede traer HTML escapado
+    pub subjects: Vec<String>,       // Un <dc:subject> por materia o etiqueta
+    pub cover_id: Option<String>,    // <meta name="cover" content="id"> (EPUB 2): item de la portada
+    // Refinamientos EPUB3 (<meta refines="#id" property="...">valor</meta>) por id
+    // referenciado (sin '#'), con sus pares (propiedad, valor) en orden de aparición
+    pub refinements: HashMap<String, Vec<(String, String)>>,
 }
 
 impl Metadata {
     // Parsea los metadatos desde el nodo <metadata> del archivo OPF
     pub fn parse(metadata_node: Node) -> Result<Self, EpubError> {
         let mut metadata = Metadata::default();
+        // ID del <meta property="belongs-to-collection"> (EPUB3) para buscar su posición
+        let mut collection_id: Option<&str> = None;
+        // IDs de cada <dc:creator> (paralelo a creators) para buscar roles en refinamientos EPUB3
+        let mut creator_ids: Vec<Option<&str>> = Vec::new();
 
         for child in metadata_node.children().filter(Node::is_element) {
             // Usamos local_name() para ignorar prefijos de namespace (dc:, etc.)
             match child.tag_name().name() {
                 "title" => metadata.title = child.text().map(str::to_string),
-                "creator" => metadata.creator = child.text().map(str::to_string),
+                "creator" => {
+                    let name = child.text().map(str::trim).unwrap_or("");
+                    if !name.is_empty() {
+                        // EPUB2 usa el atributo opf:role; lo buscamos por nombre local
+                        let role = child.attributes()
+                            .find(|attr| attr.name() == "role")
+                            .map(|attr| attr.value().to_string());
+                        metadata.creators.push(Creator { name: name.to_string(), role });
+                        creator_ids.push(child.attribute("id"));
+                    }
+                }
                 "language" => metadata.language = child.text().map(str::to_string),
                 "identifier" => metadata.identifier = child.text().map(str::to_string),
                 "publisher" => metadata.publisher = child.text().map(str::to_string),
-                "date" => metadata.date = child.text().map(str::to_string),
+                "date" => {
+                    let date = child.text().map(str::trim).filter(|text| !text.is_empty());
+                    // EPUB2 distingue las fechas con opf:event; sin él se toma como publicación
+                    let event = child.attributes()
+                        .find(|attr| attr.name() == "event")
+                        .map(|attr| attr.value().to_lowercase());
+                    match event.as_deref() {
+                        Some("publication") => metadata.date = date.map(str::to_string).or(metadata.date),
+                        Some("modification") if metadata.modified.is_none() => {
+                            metadata.modified = date.map(str::to_string);
+                        }
+                        None if metadata.date.is_none() => metadata.date = date.map(str::to_string),
+                        _ => {}
+                    }
+                }
+                "description" => {
+                    metadata.description = child.text()
+                        .map(str::trim)
+                        .filter(|text| !text.is_empty())
+                        .map(str::to_string);
+                }
+                "subject" => {
+                    if let Some(subject) = child.text().map(str::trim).filter(|text| !text.is_empty()) {
+                        metadata.subjects.push(subject.to_string());
+                    }
+                }
+                "meta" if child.attribute("refines").is_some() => {
+                    if let (Some(refines), Some(property)) = (child.attribute("refines"), child.attribute("property")) {
+                        let value = child.text().map(str::trim).unwrap_or("").to_string();
+                        metadata.refinements
+                            .entry(refines.trim().trim_start_matches('#').to_string())
+                            .or_default()
+                            .push((property.to_string(), value));
+                    }
+                }
+                "meta" => match (child.attribute("name"), child.attribute("property")) {
+                    (Some("cover"), _) => {
+                        metadata.cover_id = child.attribute("content").map(str::to_string);
+                    }
+                    // Convención de calibre
+                    (Some("calibre:series"), _) => {
+                        metadata.series = child.attribute("content").map(str::to_string);
+                    }
+                    (Some("calibre:series_index"), _) => {
+                        metadata.series_index = child.attribute("content")
+                            .and_then(|index| index.trim().parse().ok());
+                    }
+                    (_, Some("dcterms:modified")) => {
+                        metadata.modified = child.text()
+                            .map(str::trim)
+                            .filter(|text| !text.is_empty())
+                            .map(str::to_string);
+                    }
+                    // Colección EPUB3; calibre tiene prioridad si ambos están presentes
+                    (_, Some("belongs-to-collection")) if metadata.series.is_none() => {
+                        metadata.series = child.text().map(|t| t.trim().to_string());
+                        collection_id = child.attribute("id");
+                    }
+                    _ => {}
+                },
                 _ => {} // Ignora otros elementos de metadatos por ahora
             }
         }
+
+        // La posición dentro de la colección EPUB3 va en un <meta refines="#id" property="group-position">
+        if let (Some(id), None) = (collection_id, metadata.series_index) {
+            metadata.series_index = metadata.refinement(id, "group-position")
+                .and_then(|index| index.parse().ok());
+        }
+
+        // EPUB3 declara el rol con <meta refines="#id" property="role">
+        for (creator, id) in metadata.creators.iter_mut().zip(creator_ids) {
+            if let (None, Some(id)) = (&creator.role, id) {
+                creator.role = find_refinement(&metadata.refinements, id, "role");
+            }
+        }
+
         Ok(metadata)
     }
+
+    // Valor de la primera propiedad que refina al elemento con ese id
+    pub fn refinement(&self, id: &str, property: &str) -> Option<String> {
+        find_refinement(&self.refinements, id, property)
+    }
+
+    // Nombres de todos los autores unidos ("A", "A y B" o "A, B y C"), indicando el rol de
+    // quienes no son autores, p. ej. "A y B (trl)"
+    pub fn creators_display(&self, lang: Lang) -> Option<String> {
+        let names: Vec<String> = self.creators.iter()
+            .map(|c| match c.role.as_deref() {
+                Some(role) if role != "aut" => format!("{} ({})", c.name, role),
+                _ => c.name.clone(),
+            })
+            .collect();
+        join_names(&names, lang)
+    }
+
+    // Indica si el idioma del libro se escribe de derecha a izquierda (árabe, hebreo...)
+    pub fn is_rtl_language(&self) -> bool {
+        let Some(language) = self.language.as_deref() else {
+            return false;
+        };
+        // Solo importa el código principal: "ar-EG" -> "ar"
+        let primary = language.trim().split(['-', '_']).next().unwrap_or("").to_lowercase();
+        matches!(primary.as_str(), "ar" | "he" | "iw" | "fa" | "ur" | "yi" | "ps" | "sd" | "ug" | "dv")
+    }
+
+    // Serie y posición en un formato legible, p. ej. "Fundación (#2)"
+    pub fn series_display(&self) -> Option<String> {
+        let series = self.series.as_deref()?;
+        Some(match self.series_index {
+            Some(index) => format!("{} (#{})", series, index),
+            None => series.to_string(),
+        })
+    }
+}
+
+// Busca el valor de un <meta refines="#id" property="..."> (refinamiento EPUB3)
+fn find_refinement(refinements: &HashMap<String, Vec<(String, String)>>, id: &str, property: &str) -> Option<String> {
+    refinements.get(id)?
+        .iter()
+        .find(|(name, _)| name == property)
+        .map(|(_, value)| value.clone())
+}
+
+// Une nombres en una enumeración: "A, B y C" ("A, B and C" en inglés)
+fn join_names<S: AsRef<str>>(names: &[S], lang: Lang) -> Option<String> {
+    match names {
+        [] => None,
+        [single] => Some(single.as_ref().to_string()),
+        [rest @ .., last] => {
+            let rest: Vec<&str> = rest.iter().map(AsRef::as_ref).collect();
+            Some(format!("{} {} {}", rest.join(", "), lang.text(Msg::And), last.as_ref()))
+        }
+    }
 }
 
 // Función para mostrar los metadatos de forma legible
 #[allow(dead_code)]
-pub fn display_metadata(metadata: &Metadata) {
-    println!("--- Metadatos ---");
-    println!("Título: {}", metadata.title.as_deref().unwrap_or("N/A"));
-    println!("Autor: {}", metadata.creator.as_deref().unwrap_or("N/A"));
-    println!("Idioma: {}", metadata.language.as_deref().unwrap_or("N/A"));
-    println!("Identificador: {}", metadata.identifier.as_deref().unwrap_or("N/A"));
-    println!("Editor: {}", metadata.publisher.as_deref().unwrap_or("N/A"));
-    println!("Fecha: {}", metadata.date.as_deref().unwrap_or("N/A"));
+pub fn display_metadata(metadata: &Metadata, lang: Lang) {
+    let na = lang.text(Msg::NotAvailable);
+    println!("--- {} ---", lang.text(Msg::Metadata));
+    println!("{}: {}", lang.text(Msg::Title), metadata.title.as_deref().unwrap_or(na));
+    println!("{}: {}", lang.text(Msg::Author), metadata.creators_display(lang).as_deref().unwrap_or(na));
+    println!("{}: {}", lang.text(Msg::Language), metadata.language.as_deref().unwrap_or(na));
+    println!("{}: {}", lang.text(Msg::Identifier), metadata.identifier.as_deref().unwrap_or(na));
+    println!("{}: {}", lang.text(Msg::Publisher), metadata.publisher.as_deref().unwrap_or(na));
+    println!("{}: {}", lang.text(Msg::Date), metadata.date.as_deref().unwrap_or(na));
+    println!("{}: {}", lang.text(Msg::Modified), metadata.modified.as_deref().unwrap_or(na));
+    println!("{}: {}", lang.text(Msg::Series), metadata.series_display().as_deref().unwrap_or(na));
     println!("---------------");
 }
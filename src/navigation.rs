@@ -1,16 +1,70 @@
 // src/navigation.rs
 use std::collections::HashMap;
+use std::fmt;
+use serde::Serialize;
 use crate::epub::ManifestItem; // Necesitaremos esto más tarde
 use crate::errors::EpubError;
+use crate::path_utils;
 
 // Representa una entrada en la Tabla de Contenidos (TOC)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TocEntry {
     pub label: String,
-    #[allow(dead_code)]
-    pub href: String, // Ruta resuelta dentro del EPUB
-    #[allow(dead_code)]
+    pub href: String, // Ruta resuelta dentro del EPUB (sin fragmento)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fragment: Option<String>, // Ancla dentro del archivo, si la hay
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<String>, // ID opcional del navPoint/li
+    pub depth: usize, // Nivel de anidamiento en la TOC (1 = nivel superior)
+    // Subentradas en el árbol de la TOC; siempre vacío en la lista aplanada
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TocEntry>,
+}
+
+// Aplana el árbol de la TOC en orden de lectura (cada entrada seguida de sus
+// subentradas). Las entradas conservan su profundidad y pierden los hijos
+pub fn flatten_toc(tree: &[TocEntry]) -> Vec<TocEntry> {
+    fn visit(entries: &[TocEntry], flat: &mut Vec<TocEntry>) {
+        for entry in entries {
+            flat.push(TocEntry {
+                label: entry.label.clone(),
+                href: entry.href.clone(),
+                fragment: entry.fragment.clone(),
+                id: entry.id.clone(),
+                depth: entry.depth,
+                children: Vec::new(),
+            });
+            visit(&entry.children, flat);
+        }
+    }
+    let mut flat = Vec::new();
+    visit(tree, &mut flat);
+    flat
+}
+
+// Una línea del esquema de la TOC: sangrada según la profundidad, "etiqueta (href)"
+impl fmt::Display for TocEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let indent = "  ".repeat(self.depth.saturating_sub(1));
+        write!(f, "{}{} ({})", indent, self.label, self.href)
+    }
+}
+
+// Una página impresa de la lista de páginas (page-list) del EPUB3
+#[derive(Debug, Clone)]
+pub struct PageEntry {
+    pub label: String, // Número de página tal como aparece en el libro ("12", "xiv"...)
+    pub href: String, // Ruta resuelta dentro del EPUB (sin fragmento)
+    pub fragment: Option<String>, // Ancla dentro del archivo, si la hay
+}
+
+// Un punto de referencia del libro (cubierta, índice, comienzo del texto...)
+#[derive(Debug, Clone)]
+pub struct Landmark {
+    pub label: String,
+    pub kind: String, // epub:type de la landmark ("bodymatter", "toc", "cover"...)
+    pub href: String, // Ruta resuelta dentro del EPUB (sin fragmento)
+    pub fragment: Option<String>,
 }
 
 // Gestiona el estado de la navegación
@@ -18,55 +72,176 @@ pub struct TocEntry {
 pub struct Navigator {
     // Items en el orden de lectura definido por <spine>
     spine_ids: Vec<String>,
+    // Paralelo a spine_ids: los items con linear="no" se saltan en la lectura secuencial
+    spine_linear: Vec<bool>,
     // Índice actual dentro de spine_ids
     current_spine_index: usize,
-    // Tabla de contenidos para mostrar al usuario (puede no coincidir 1:1 con el spine)
+    // Tabla de contenidos aplanada para mostrar al usuario (puede no coincidir 1:1 con el
+    // spine). Los índices de TOC de toda la navegación se refieren a esta lista
     toc: Vec<TocEntry>,
+    // La misma TOC como árbol
+    toc_tree: Vec<TocEntry>,
     // Mapa para buscar rápidamente hrefs desde IDs (del manifiesto)
     manifest: HashMap<String, ManifestItem>,
     // Directorio base para resolver rutas relativas (directorio del OPF)
     root_path: String,
+    // Índice del spine al que apunta cada entrada de la TOC (por índice de TOC)
+    toc_to_spine: HashMap<usize, usize>,
+    // Entradas de la TOC (en orden) que apuntan a cada item del spine;
+    // un mismo archivo puede tener varias secciones en la TOC
+    spine_to_toc: HashMap<usize, Vec<usize>>,
+    // Problemas encontrados al validar el spine (items que no están en el manifiesto)
+    warnings: Vec<String>,
 }
 
 impl Navigator {
     pub fn new(
         spine_ids: Vec<String>,
-        toc: Vec<TocEntry>,
+        spine_linear: Vec<bool>,
+        toc_tree: Vec<TocEntry>,
         manifest: HashMap<String, ManifestItem>,
         root_path: String,
     ) -> Self {
-        Navigator {
+        // Un itemref cuyo id no está en el manifiesto no se puede leer. Se conserva para
+        // que los números de capítulo sigan los del spine, pero la navegación lo salta
+        let warnings = spine_ids.iter()
+            .filter(|id| !manifest.contains_key(id.as_str()))
+            .map(|id| format!("El item '{}' del spine no está en el manifiesto y se omite", id))
+            .collect();
+        let first_readable = spine_ids.iter().position(|id| manifest.contains_key(id)).unwrap_or(0);
+
+        let mut navigator = Navigator {
             spine_ids,
-            current_spine_index: 0,
-            toc,
+            spine_linear,
+            current_spine_index: first_readable,
+            toc: flatten_toc(&toc_tree),
+            toc_tree,
             manifest,
             root_path,
+            toc_to_spine: HashMap::new(),
+            spine_to_toc: HashMap::new(),
+            warnings,
+        };
+        navigator.build_toc_mapping();
+        navigator
+    }
+
+    // Advertencias recogidas al construir el navegador
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    // Precalcula la correspondencia entre entradas de la TOC e items del spine
+    // comparando rutas normalizadas, para no repetir búsquedas O(n·m)
+    fn build_toc_mapping(&mut self) {
+        let mut spine_by_href: HashMap<String, usize> = HashMap::new();
+        for spine_index in 0..self.spine_ids.len() {
+            if let Ok(href) = self.spine_href(spine_index) {
+                spine_by_href.entry(href).or_insert(spine_index);
+            }
+        }
+
+        for (toc_index, entry) in self.toc.iter().enumerate() {
+            if let Some(&spine_index) = spine_by_href.get(&entry.href) {
+                self.toc_to_spine.insert(toc_index, spine_index);
+                self.spine_to_toc.entry(spine_index).or_default().push(toc_index);
+            }
         }
     }
 
-    // Avanza al siguiente capítulo en el spine
+    // Primera entrada de la TOC que apunta al item del spine indicado
+    pub fn toc_index_for_spine(&self, spine_index: usize) -> Option<usize> {
+        self.spine_to_toc.get(&spine_index).and_then(|entries| entries.first().copied())
+    }
+
+    // Etiqueta de la primera entrada de la TOC que apunta al item del spine, si la hay
+    pub fn toc_label_for_spine(&self, spine_index: usize) -> Option<&str> {
+        self.toc_index_for_spine(spine_index).map(|toc_index| self.toc[toc_index].label.as_str())
+    }
+
+    // Item del spine al que apunta la entrada de la TOC indicada
+    pub fn spine_index_for_toc(&self, toc_index: usize) -> Option<usize> {
+        self.toc_to_spine.get(&toc_index).copied()
+    }
+
+    // Va al item del spine de la entrada de la TOC indicada (basada en 0). Devuelve false si
+    // la entrada no existe o no apunta a ningún item del spine
+    pub fn goto_toc(&mut self, toc_index: usize) -> bool {
+        match self.spine_index_for_toc(toc_index) {
+            Some(spine_index) => {
+                self.current_spine_index = spine_index;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Fragmento (#id) de la entrada de la TOC, si lo tiene, para desplazarse hasta él
+    pub fn toc_fragment(&self, toc_index: usize) -> Option<&str> {
+        self.toc.get(toc_index)?.fragment.as_deref()
+    }
+
+    // Entradas de la TOC cuyo título contiene el texto (sin distinguir mayúsculas) y que
+    // llevan a algún item del spine, en orden
+    pub fn find_toc_label(&self, query: &str) -> Vec<usize> {
+        let query = query.to_lowercase();
+        (0..self.toc.len())
+            .filter(|&toc_index| self.toc[toc_index].label.to_lowercase().contains(&query))
+            .filter(|&toc_index| self.spine_index_for_toc(toc_index).is_some())
+            .collect()
+    }
+
+    // Indica si el item del spine forma parte del flujo de lectura principal
+    fn is_linear(&self, spine_index: usize) -> bool {
+        self.spine_linear.get(spine_index).copied().unwrap_or(true)
+    }
+
+    // Indica si el item del spine está en el manifiesto (si no, no hay archivo que leer)
+    fn is_readable(&self, spine_index: usize) -> bool {
+        self.spine_ids.get(spine_index).is_some_and(|id| self.manifest.contains_key(id))
+    }
+
+    // Avanza al siguiente capítulo lineal en el spine
     pub fn next(&mut self) -> bool {
-        if self.current_spine_index + 1 < self.spine_ids.len() {
-            self.current_spine_index += 1;
-            true
-        } else {
-            false // Ya está en el último capítulo
+        match (self.current_spine_index + 1..self.spine_ids.len()).find(|&i| self.is_linear(i) && self.is_readable(i)) {
+            Some(index) => {
+                self.current_spine_index = index;
+                true
+            }
+            None => false, // Ya está en el último capítulo
         }
     }
 
-    // Retrocede al capítulo anterior en el spine
+    // Retrocede al capítulo lineal anterior en el spine
     pub fn prev(&mut self) -> bool {
-        if self.current_spine_index > 0 {
-            self.current_spine_index -= 1;
-            true
-        } else {
-            false // Ya está en el primer capítulo
+        match (0..self.current_spine_index).rev().find(|&i| self.is_linear(i) && self.is_readable(i)) {
+            Some(index) => {
+                self.current_spine_index = index;
+                true
+            }
+            None => false, // Ya está en el primer capítulo
         }
     }
 
-    // Va a un capítulo específico por su índice (basado en 1 para el usuario)
+    // Primer item lineal del spine después del actual (o antes, con `backwards`) para el que
+    // `has_content` devuelve true al pasarle su ruta. Cada item se comprueba como mucho una
+    // vez, así que termina aunque ningún capítulo tenga contenido
+    pub fn find_non_empty(&self, backwards: bool, mut has_content: impl FnMut(&str) -> bool) -> Option<usize> {
+        let candidates: Box<dyn Iterator<Item = usize>> = if backwards {
+            Box::new((0..self.current_spine_index).rev())
+        } else {
+            Box::new(self.current_spine_index + 1..self.spine_ids.len())
+        };
+        candidates
+            .filter(|&index| self.is_linear(index))
+            .find(|&index| self.spine_href(index).is_ok_and(|href| has_content(&href)))
+    }
+
+    // Va a un capítulo específico por su índice (basado en 1 para el usuario).
+    // Permite ir también a items no lineales (notas, anexos...), pero no a los que faltan
+    // en el manifiesto
     pub fn goto(&mut self, index_one_based: usize) -> bool {
-        if index_one_based > 0 && index_one_based <= self.spine_ids.len() {
+        if index_one_based > 0 && self.is_readable(index_one_based - 1) {
             self.current_spine_index = index_one_based - 1;
             true
         } else {
@@ -81,22 +256,47 @@ impl Navigator {
 
     // Obtiene la ruta (href) del capítulo actual
     pub fn current_chapter_href(&self) -> Result<String, EpubError> {
-        let id = self.current_chapter_id()
-            .ok_or_else(|| EpubError::InvalidChapterIndex(self.current_spine_index))?;
+        self.spine_href(self.current_spine_index)
+    }
+
+    // Busca el índice del spine del item del manifiesto con ese id
+    pub fn spine_index_for_id(&self, id: &str) -> Option<usize> {
+        self.spine_ids.iter().position(|spine_id| spine_id == id)
+    }
+
+    // Busca el índice del spine cuyo archivo coincide con una ruta completa del ZIP
+    pub fn spine_index_for_href(&self, href: &str) -> Option<usize> {
+        let href = path_utils::strip_fragment(href);
+        (0..self.spine_ids.len())
+            .find(|&index| self.spine_href(index).is_ok_and(|spine_href| spine_href == href))
+    }
+
+    // Obtiene la ruta completa dentro del ZIP de un item del spine
+    pub fn spine_href(&self, spine_index: usize) -> Result<String, EpubError> {
+        let id = self.spine_ids.get(spine_index)
+            .ok_or(EpubError::InvalidChapterIndex(spine_index))?;
 
         let manifest_item = self.manifest.get(id)
             .ok_or_else(|| EpubError::ManifestItemNotFound(id.to_string()))?;
 
-        // Construye la ruta completa dentro del archivo ZIP
         // self.root_path es el directorio que contiene el OPF
         // manifest_item.href es relativo a ese directorio
-        let full_path = if self.root_path.is_empty() {
-            manifest_item.href.clone()
-        } else {
-            format!("{}/{}", self.root_path, manifest_item.href)
-        };
+        Ok(path_utils::join(&self.root_path, &manifest_item.href))
+    }
 
-        Ok(full_path.replace("//", "/")) // Simple normalización
+    // Etiqueta legible del capítulo actual según la TOC. Como TOC y spine no
+    // coinciden 1:1, si el archivo actual no tiene entrada propia se usa la
+    // última entrada de TOC del archivo anterior más cercano que sí la tenga
+    pub fn current_chapter_label(&self) -> Option<&str> {
+        (0..=self.current_spine_index).rev().find_map(|spine_index| {
+            let entries = self.spine_to_toc.get(&spine_index)?;
+            let toc_index = if spine_index == self.current_spine_index {
+                entries.first()
+            } else {
+                entries.last()
+            };
+            toc_index.map(|&toc_index| self.toc[toc_index].label.as_str())
+        })
     }
 
 
@@ -113,14 +313,8 @@ pub fn display_toc(&self) {
         println!(" (No se encontró o no se pudo parsear la tabla de contenidos)");
     } else {
         for (i, entry) in self.toc.iter().enumerate() {
-            // Intentamos encontrar el índice del spine que corresponde a este href
-            // Esto es una aproximación, ya que TOC y Spine no siempre coinciden perfectamente
-            let spine_index = self.spine_ids.iter().position(|id| {
-                self.manifest.get(id).map_or(false, |item| {
-                   let item_full_path = if self.root_path.is_empty() { item.href.clone() } else { format!("{}/{}", self.root_path, item.href) };
-                   item_full_path.replace("//", "/") == entry.href
-                })
-            });
+            // Índice del spine que corresponde a este href (TOC y spine no siempre coinciden)
+            let spine_index = self.spine_index_for_toc(i);
             if let Some(idx) = spine_index {
                  println!("{:>3}. {} (Ir con: goto {})", i + 1, entry.label, idx + 1);
             } else {
@@ -142,19 +336,214 @@ pub fn display_toc(&self) {
     pub fn get_toc(&self) -> &Vec<TocEntry> {
         &self.toc
     }
-}
 
-// Función helper (simplificada) para normalizar rutas (maneja "./", asume separador '/')
-// Una librería como `path_clean` o `lexiclean` sería más robusta
-#[allow(dead_code)]
-fn normalize_path_simple(path_str: &str) -> String {
-    let mut components: Vec<&str> = Vec::new();
-    for component in path_str.split('/') {
-        match component {
-            "." | "" => {} // Ignorar componente actual o vacío
-            ".." => { components.pop(); } // Subir un nivel
-            _ => components.push(component), // Añadir componente normal
+    // Cambia la etiqueta de una entrada de la TOC, en la lista aplanada y en el árbol
+    pub fn set_toc_label(&mut self, toc_index: usize, label: String) {
+        fn find<'a>(entries: &'a mut [TocEntry], target: usize, next: &mut usize) -> Option<&'a mut TocEntry> {
+            for entry in entries {
+                if *next == target {
+                    return Some(entry);
+                }
+                *next += 1;
+                if let Some(found) = find(&mut entry.children, target, next) {
+                    return Some(found);
+                }
+            }
+            None
         }
+        if let Some(node) = find(&mut self.toc_tree, toc_index, &mut 0) {
+            node.label = label.clone();
+        }
+        if let Some(entry) = self.toc.get_mut(toc_index) {
+            entry.label = label;
+        }
+    }
+
+    // Devuelve la tabla de contenidos como árbol
+    pub fn toc_tree(&self) -> &[TocEntry] {
+        &self.toc_tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest_item(id: &str, href: &str) -> (String, ManifestItem) {
+        let item = ManifestItem {
+            id: id.to_string(),
+            href: href.to_string(),
+            media_type: "application/xhtml+xml".to_string(),
+            properties: None,
+        };
+        (id.to_string(), item)
+    }
+
+    fn toc_entry(label: &str, href: &str) -> TocEntry {
+        TocEntry { label: label.to_string(), href: href.to_string(), fragment: None, id: None, depth: 1, children: Vec::new() }
+    }
+
+    // Tres archivos en el spine; el segundo tiene dos secciones en la TOC y el tercero ninguna
+    fn sample_navigator() -> Navigator {
+        let manifest = [
+            manifest_item("c1", "Text/c1.xhtml"),
+            manifest_item("c2", "Text/c2.xhtml"),
+            manifest_item("c3", "Text/c3.xhtml"),
+        ].into_iter().collect();
+        let toc = vec![
+            toc_entry("Uno", "OEBPS/Text/c1.xhtml"),
+            toc_entry("Dos", "OEBPS/Text/c2.xhtml"),
+            toc_entry("Dos bis", "OEBPS/Text/c2.xhtml"),
+            toc_entry("Fuera del spine", "OEBPS/Text/extra.xhtml"),
+        ];
+        Navigator::new(
+            vec!["c1".to_string(), "c2".to_string(), "c3".to_string()],
+            vec![true; 3],
+            toc,
+            manifest,
+            "OEBPS".to_string(),
+        )
+    }
+
+    #[test]
+    fn maps_toc_entries_to_spine() {
+        let navigator = sample_navigator();
+        assert_eq!(navigator.spine_index_for_toc(0), Some(0));
+        assert_eq!(navigator.spine_index_for_toc(1), Some(1));
+        assert_eq!(navigator.spine_index_for_toc(2), Some(1));
+        assert_eq!(navigator.spine_index_for_toc(3), None);
+    }
+
+    #[test]
+    fn maps_spine_to_first_toc_entry() {
+        let navigator = sample_navigator();
+        assert_eq!(navigator.toc_index_for_spine(0), Some(0));
+        assert_eq!(navigator.toc_index_for_spine(1), Some(1));
+        assert_eq!(navigator.toc_index_for_spine(2), None);
+    }
+
+    #[test]
+    fn finds_next_chapter_with_content_in_either_direction() {
+        let mut navigator = sample_navigator();
+        let has_content = |href: &str| !href.ends_with("c2.xhtml");
+        assert_eq!(navigator.find_non_empty(false, has_content), Some(2));
+        assert_eq!(navigator.find_non_empty(true, has_content), None);
+        navigator.goto(3);
+        assert_eq!(navigator.find_non_empty(true, has_content), Some(0));
+        assert_eq!(navigator.find_non_empty(true, |_| false), None);
+    }
+
+    #[test]
+    fn finds_toc_entries_by_label() {
+        let navigator = sample_navigator();
+        assert_eq!(navigator.find_toc_label("DOS"), vec![1, 2]);
+        assert_eq!(navigator.find_toc_label("bis"), vec![2]);
+        // Las entradas que no llevan a ningún capítulo no cuentan
+        assert!(navigator.find_toc_label("fuera").is_empty());
+    }
+
+    #[test]
+    fn finds_spine_index_by_manifest_id() {
+        let navigator = sample_navigator();
+        assert_eq!(navigator.spine_index_for_id("c3"), Some(2));
+        assert_eq!(navigator.spine_index_for_id("C3"), None);
+        assert_eq!(navigator.spine_index_for_id("extra"), None);
+    }
+
+    #[test]
+    fn goto_toc_moves_to_spine_item_and_exposes_fragment() {
+        let mut navigator = sample_navigator();
+        navigator.toc[2].fragment = Some("sec2".to_string());
+        assert!(navigator.goto_toc(2));
+        assert_eq!(navigator.current_position().0, 2);
+        assert_eq!(navigator.toc_fragment(2), Some("sec2"));
+        assert!(navigator.goto_toc(0));
+        assert_eq!(navigator.current_position().0, 1);
+        assert_eq!(navigator.toc_fragment(0), None);
+        // Fuera del spine o inexistente: no se mueve
+        assert!(!navigator.goto_toc(3));
+        assert!(!navigator.goto_toc(10));
+        assert_eq!(navigator.toc_fragment(10), None);
+        assert_eq!(navigator.current_position().0, 1);
+    }
+
+    #[test]
+    fn labels_spine_items_by_their_toc_entry() {
+        let navigator = sample_navigator();
+        assert_eq!(navigator.toc_label_for_spine(1), Some("Dos"));
+        assert_eq!(navigator.toc_label_for_spine(2), None);
+        assert_eq!(navigator.spine_href(2).unwrap(), "OEBPS/Text/c3.xhtml");
+    }
+
+    #[test]
+    fn label_falls_back_to_last_section_of_previous_file() {
+        let mut navigator = sample_navigator();
+        assert_eq!(navigator.current_chapter_label(), Some("Uno"));
+        navigator.goto(2);
+        assert_eq!(navigator.current_chapter_label(), Some("Dos"));
+        navigator.goto(3);
+        assert_eq!(navigator.current_chapter_label(), Some("Dos bis"));
+    }
+
+    #[test]
+    fn skips_spine_items_missing_from_manifest() {
+        let manifest = [
+            manifest_item("c1", "Text/c1.xhtml"),
+            manifest_item("c3", "Text/c3.xhtml"),
+        ].into_iter().collect();
+        let mut navigator = Navigator::new(
+            vec!["c1".to_string(), "roto".to_string(), "c3".to_string()],
+            vec![true, true, false],
+            Vec::new(),
+            manifest,
+            "OEBPS".to_string(),
+        );
+        // Los números de capítulo siguen siendo los del spine
+        assert_eq!(navigator.total_chapters(), 3);
+        assert_eq!(navigator.warnings().len(), 1);
+        assert!(navigator.warnings()[0].contains("roto"));
+        // next salta el roto y el no lineal; goto no entra en el roto
+        assert!(!navigator.next());
+        assert!(!navigator.goto(2));
+        assert!(navigator.goto(3));
+        assert_eq!(navigator.current_chapter_href().unwrap(), "OEBPS/Text/c3.xhtml");
+        assert!(navigator.prev());
+        assert_eq!(navigator.current_position(), (1, 3));
+    }
+
+    #[test]
+    fn flat_toc_indices_follow_the_tree_in_preorder() {
+        // La UI recorre toc_tree() y usa la posición en preorden como índice de get_toc()
+        let mut part = toc_entry("Parte", "OEBPS/Text/c1.xhtml");
+        let mut section = toc_entry("Sección", "OEBPS/Text/c2.xhtml");
+        section.depth = 2;
+        part.children = vec![section, toc_entry("Otra", "OEBPS/Text/c2.xhtml")];
+        part.children[1].depth = 2;
+        let tree = vec![part, toc_entry("Epílogo", "OEBPS/Text/c3.xhtml")];
+        let navigator = Navigator::new(Vec::new(), Vec::new(), tree, HashMap::new(), "OEBPS".to_string());
+        let labels: Vec<&str> = navigator.get_toc().iter().map(|entry| entry.label.as_str()).collect();
+        assert_eq!(labels, ["Parte", "Sección", "Otra", "Epílogo"]);
+        assert_eq!(navigator.toc_tree().len(), 2);
+        assert_eq!(navigator.toc_tree()[0].children.len(), 2);
+        assert!(navigator.get_toc().iter().all(|entry| entry.children.is_empty()));
+    }
+
+    #[test]
+    fn set_toc_label_updates_list_and_tree() {
+        let mut part = toc_entry("Parte", "OEBPS/Text/c1.xhtml");
+        part.children = vec![toc_entry("", "OEBPS/Text/c2.xhtml")];
+        part.children[0].depth = 2;
+        let mut navigator = Navigator::new(Vec::new(), Vec::new(), vec![part], HashMap::new(), "OEBPS".to_string());
+        navigator.set_toc_label(1, "Sección".to_string());
+        assert_eq!(navigator.get_toc()[1].label, "Sección");
+        assert_eq!(navigator.toc_tree()[0].children[0].label, "Sección");
+    }
+
+    #[test]
+    fn toc_entry_displays_as_indented_outline() {
+        let mut entry = toc_entry("Sección", "OEBPS/Text/c2.xhtml");
+        assert_eq!(entry.to_string(), "Sección (OEBPS/Text/c2.xhtml)");
+        entry.depth = 3;
+        assert_eq!(entry.to_string(), "    Sección (OEBPS/Text/c2.xhtml)");
     }
-    components.join("/")
 }
@@ -0,0 +1,122 @@
+// src/path_utils.rs
+// Rutas dentro del ZIP de un EPUB. Siempre usan '/' como separador, así que se
+// tratan como cadenas y no con std::path (que dependería del sistema operativo)
+
+// Normaliza una ruta: convierte '\' en '/', elimina componentes vacíos y "." y
+// resuelve ".." (sin subir más allá de la raíz). Conserva la '/' inicial si la hay
+pub fn normalize(path: &str) -> String {
+    let unified = path.replace('\\', "/");
+    let mut components: Vec<&str> = Vec::new();
+    for component in unified.split('/') {
+        match component {
+            "." | "" => {} // Componente actual o vacío (p. ej. "a//b")
+            ".." => { components.pop(); } // Subir un nivel; en la raíz no hace nada
+            _ => components.push(component),
+        }
+    }
+
+    let prefix = if unified.starts_with('/') { "/" } else { "" };
+    format!("{}{}", prefix, components.join("/"))
+}
+
+// Une un directorio base con un href relativo y normaliza el resultado.
+// Un href que empieza por '/' es absoluto desde la raíz del ZIP y no usa la base
+pub fn join(base: &str, relative: &str) -> String {
+    if let Some(absolute) = relative.strip_prefix('/') {
+        return normalize(absolute);
+    }
+    if base.is_empty() {
+        normalize(relative)
+    } else {
+        normalize(&format!("{}/{}", base, relative))
+    }
+}
+
+// Directorio que contiene un archivo ("OEBPS/Text/ch1.xhtml" -> "OEBPS/Text")
+pub fn parent(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(dir, _)| dir)
+}
+
+// Quita el fragmento de un href ("ch1.xhtml#sec2" -> "ch1.xhtml")
+pub fn strip_fragment(href: &str) -> &str {
+    href.split_once('#').map_or(href, |(path, _)| path)
+}
+
+// Resuelve un href encontrado dentro de `file_path` (un nav.xhtml, un capítulo...)
+// a una ruta completa del ZIP, sin el fragmento
+pub fn resolve(file_path: &str, href: &str) -> String {
+    join(parent(file_path), strip_fragment(href))
+}
+
+// Decodifica las secuencias %XX de un href ("Mi%20cap%C3%ADtulo.xhtml" -> "Mi capítulo.xhtml").
+// Las secuencias inválidas se dejan tal cual
+pub fn percent_decode(href: &str) -> String {
+    let bytes = href.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).unwrap_or_else(|_| href.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_handles_dots_and_slashes() {
+        assert_eq!(normalize("a//b/./c"), "a/b/c");
+        assert_eq!(normalize("./Text/ch.xhtml"), "Text/ch.xhtml");
+        assert_eq!(normalize("a/b/../c"), "a/c");
+        assert_eq!(normalize("a\\b"), "a/b");
+        assert_eq!(normalize("/a/b"), "/a/b");
+        assert_eq!(normalize("a/b/"), "a/b");
+    }
+
+    #[test]
+    fn normalize_does_not_climb_above_root() {
+        assert_eq!(normalize("../a"), "a");
+        assert_eq!(normalize("a/../../b"), "b");
+        assert_eq!(normalize(".."), "");
+    }
+
+    #[test]
+    fn join_resolves_relative_hrefs() {
+        assert_eq!(join("", "ch1.xhtml"), "ch1.xhtml");
+        assert_eq!(join("OEBPS", "ch1.xhtml"), "OEBPS/ch1.xhtml");
+        assert_eq!(join("OEBPS", "./Text/ch1.xhtml"), "OEBPS/Text/ch1.xhtml");
+        assert_eq!(join("OEBPS/Text", "../Images/a.png"), "OEBPS/Images/a.png");
+        assert_eq!(join("OEBPS/", "Text//ch1.xhtml"), "OEBPS/Text/ch1.xhtml");
+        assert_eq!(join("OEBPS/Text", "/Images/a.png"), "Images/a.png");
+    }
+
+    #[test]
+    fn resolve_uses_directory_of_file_and_drops_fragment() {
+        assert_eq!(resolve("OEBPS/nav.xhtml", "Text/ch1.xhtml#sec2"), "OEBPS/Text/ch1.xhtml");
+        assert_eq!(resolve("OEBPS/Text/ch1.xhtml", "../Text/ch2.xhtml"), "OEBPS/Text/ch2.xhtml");
+        assert_eq!(resolve("toc.ncx", "ch1.xhtml"), "ch1.xhtml");
+        assert_eq!(parent("OEBPS/Text/ch1.xhtml"), "OEBPS/Text");
+        assert_eq!(parent("content.opf"), "");
+    }
+
+    #[test]
+    fn percent_decode_handles_utf8_and_invalid_sequences() {
+        assert_eq!(percent_decode("Text/Mi%20cap%C3%ADtulo.xhtml"), "Text/Mi capítulo.xhtml");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("a%zzb"), "a%zzb");
+        assert_eq!(percent_decode("sin-escapes.xhtml"), "sin-escapes.xhtml");
+    }
+}
@@ -0,0 +1,109 @@
+// src/recent.rs
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Número máximo de libros que se recuerdan
+const MAX_RECENT: usize = 20;
+const RECENT_FILE: &str = "recent.tsv";
+
+// Un libro abierto recientemente y la posición en la que se dejó
+#[derive(Debug, Clone)]
+pub struct RecentBook {
+    pub path: PathBuf,
+    pub title: String,
+    pub chapter: usize, // Capítulo (basado en 1, como en goto)
+    pub scroll_offset: u16,
+}
+
+impl RecentBook {
+    // Indica si el archivo sigue existiendo en disco
+    pub fn exists(&self) -> bool {
+        self.path.exists()
+    }
+}
+
+// Lista de libros recientes, guardada en el directorio de estado del usuario.
+// Formato: una línea por libro, "ruta\tcapítulo\tscroll\ttítulo", el más reciente primero
+#[derive(Debug, Default)]
+pub struct RecentBooks {
+    pub books: Vec<RecentBook>,
+}
+
+impl RecentBooks {
+    // Carga la lista; si el archivo no existe o no se puede leer, empieza vacía
+    pub fn load() -> Self {
+        let Some(path) = recent_file_path() else {
+            return RecentBooks::default();
+        };
+        let content = fs::read_to_string(path).unwrap_or_default();
+        let books = content.lines().filter_map(parse_line).collect();
+        RecentBooks { books }
+    }
+
+    // Escribe la lista en disco, creando el directorio de estado si hace falta
+    pub fn save(&self) -> io::Result<()> {
+        let path = recent_file_path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No se encontró el directorio de estado"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let content: String = self.books.iter()
+            .map(|book| format!(
+                "{}\t{}\t{}\t{}\n",
+                book.path.display(),
+                book.chapter,
+                book.scroll_offset,
+                book.title.replace(['\t', '\n'], " ")
+            ))
+            .collect();
+        fs::write(path, content)
+    }
+
+    // Busca la última posición guardada de un libro
+    pub fn find(&self, path: &Path) -> Option<&RecentBook> {
+        let path = canonical(path);
+        self.books.iter().find(|book| book.path == path)
+    }
+
+    // Registra un libro (o actualiza su posición) y lo mueve al principio de la lista
+    pub fn remember(&mut self, path: &Path, title: &str, chapter: usize, scroll_offset: u16) {
+        let path = canonical(path);
+        self.books.retain(|book| book.path != path);
+        self.books.insert(0, RecentBook {
+            path,
+            title: title.to_string(),
+            chapter,
+            scroll_offset,
+        });
+        self.books.truncate(MAX_RECENT);
+    }
+}
+
+// Directorio de estado: $XDG_STATE_HOME/epub_reader o ~/.local/state/epub_reader
+pub fn state_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+    Some(base.join("epub_reader"))
+}
+
+fn recent_file_path() -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join(RECENT_FILE))
+}
+
+// Ruta absoluta para que el mismo libro abierto desde otro directorio coincida
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn parse_line(line: &str) -> Option<RecentBook> {
+    let mut fields = line.splitn(4, '\t');
+    let path = PathBuf::from(fields.next()?);
+    let chapter = fields.next()?.parse().ok()?;
+    let scroll_offset = fields.next()?.parse().ok()?;
+    let title = fields.next().unwrap_or("").to_string();
+    Some(RecentBook { path, title, chapter, scroll_offset })
+}
@@ -0,0 +1,185 @@
+// src/render/blocks.rs
+// Renderizado estructurado: el capítulo como una lista de bloques (encabezados, párrafos,
+// elementos de lista...) con el énfasis en cada fragmento, para que la UI pueda darles
+// estilo sin volver a interpretar los prefijos "#"/"-" ni los marcadores "*" del texto plano.
+// Se construye sobre render_chapter, así que notas al pie, <q>, énfasis por CSS, pies de
+// figura y versos quedan igual que en el texto
+use super::{
+    render_chapter_with_css, ClassStyles, HeadingStyle, ListStyle, ParagraphStyle, RenderOptions,
+    StyleRange, TextStyle, DEFINITION_INDENT, LIST_INDENT, SCENE_BREAK,
+};
+
+// Un fragmento de texto con el mismo estilo
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TextSpan {
+    pub text: String,
+    pub bold: bool,
+    pub italic: bool,
+}
+
+// Un bloque del capítulo
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    Heading(u8, String),             // Nivel (1-6) y texto del encabezado
+    Paragraph(Vec<TextSpan>),        // También cada verso cortado con <br> y cada pie de figura
+    ListItem(usize, Vec<TextSpan>),  // Profundidad de anidamiento (0 = primer nivel)
+    Definition(Vec<TextSpan>),       // <dd>, sangrada bajo su término (<dt> es un párrafo en negrita)
+    SceneBreak,                      // <hr>
+}
+
+impl Block {
+    // Texto del bloque sin estilos
+    #[allow(dead_code)]
+    pub fn plain_text(&self) -> String {
+        match self {
+            Block::Heading(_, text) => text.clone(),
+            Block::Paragraph(spans) | Block::ListItem(_, spans) | Block::Definition(spans) => {
+                spans.iter().map(|span| span.text.as_str()).collect()
+            }
+            Block::SceneBreak => SCENE_BREAK.to_string(),
+        }
+    }
+}
+
+// Convierte el XHTML de un capítulo en bloques con estilo. El renderizado de texto
+// (render_xhtml_to_text_with) sigue siendo el que usan --dump-text y las exportaciones
+#[allow(dead_code)]
+pub fn render_xhtml_to_blocks(xhtml_content: &str) -> Vec<Block> {
+    render_xhtml_to_blocks_with(xhtml_content, &RenderOptions::default(), &ClassStyles::default())
+}
+
+// render_xhtml_to_blocks con las opciones de idioma (comillas, etiquetas), numeración y
+// CSS del capítulo. Los marcadores, prefijos y separación de párrafos se ignoran: los
+// bloques ya llevan esa información
+#[allow(dead_code)]
+pub fn render_xhtml_to_blocks_with(xhtml_content: &str, options: &RenderOptions, class_styles: &ClassStyles) -> Vec<Block> {
+    let options = RenderOptions {
+        emphasis_markers: false,
+        heading_style: HeadingStyle::Plain,
+        list_style: ListStyle::Dash,
+        paragraph_style: ParagraphStyle::Spaced,
+        ..options.clone()
+    };
+    let rendered = render_chapter_with_css(xhtml_content, &options, class_styles);
+    let list_prefix = ListStyle::Dash.prefix();
+
+    let mut blocks = Vec::new();
+    for (index, line) in rendered.text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let ranges: Vec<&StyleRange> = rendered.styles.iter().filter(|range| range.line == index).collect();
+        let heading = ranges.iter().find_map(|range| match range.style {
+            TextStyle::Heading(level) => Some(level),
+            _ => None,
+        });
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        let block = if line == SCENE_BREAK {
+            Block::SceneBreak
+        } else if let Some(level) = heading {
+            Block::Heading(level, line.trim().to_string())
+        } else if line[indent..].starts_with(list_prefix.trim_start()) && indent >= LIST_INDENT.len() {
+            // "  - " en el primer nivel y LIST_INDENT más por cada nivel de anidamiento
+            let depth = (indent - LIST_INDENT.len()) / LIST_INDENT.len();
+            Block::ListItem(depth, spans(line, indent + list_prefix.trim_start().len(), &ranges))
+        } else if line.starts_with(DEFINITION_INDENT) {
+            Block::Definition(spans(line, DEFINITION_INDENT.len(), &ranges))
+        } else {
+            Block::Paragraph(spans(line, indent, &ranges))
+        };
+        blocks.push(block);
+    }
+    blocks
+}
+
+// Parte la línea (desde `start`) en fragmentos según sus tramos de negrita y cursiva
+fn spans(line: &str, start: usize, ranges: &[&StyleRange]) -> Vec<TextSpan> {
+    let emphasis: Vec<&StyleRange> = ranges.iter()
+        .copied()
+        .filter(|range| matches!(range.style, TextStyle::Bold | TextStyle::Italic))
+        .collect();
+    let mut cuts: Vec<usize> = emphasis.iter()
+        .flat_map(|range| [range.start, range.end])
+        .filter(|&cut| cut > start && cut < line.len())
+        .collect();
+    cuts.extend([start, line.len()]);
+    cuts.sort_unstable();
+    cuts.dedup();
+
+    let mut spans: Vec<TextSpan> = Vec::new();
+    for segment in cuts.windows(2) {
+        let (from, to) = (segment[0], segment[1]);
+        let covers = |style: TextStyle| emphasis.iter().any(|range| range.style == style && range.start <= from && to <= range.end);
+        let (bold, italic) = (covers(TextStyle::Bold), covers(TextStyle::Italic));
+        match spans.last_mut() {
+            Some(last) if last.bold == bold && last.italic == italic => last.text.push_str(&line[from..to]),
+            _ => spans.push(TextSpan { text: line[from..to].to_string(), bold, italic }),
+        }
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(text: &str, bold: bool, italic: bool) -> TextSpan {
+        TextSpan { text: text.to_string(), bold, italic }
+    }
+
+    #[test]
+    fn builds_blocks_with_styled_spans() {
+        let chapter = "<html><body><h2>Capítulo <em>uno</em></h2>\
+            <p>Hola <em>mundo</em> y <strong>todos</strong>.</p><hr/>\
+            <ul><li>Uno</li><li>Dos<ul><li>Dos punto uno</li></ul></li></ul></body></html>";
+        assert_eq!(render_xhtml_to_blocks(chapter), vec![
+            Block::Heading(2, "Capítulo uno".to_string()),
+            Block::Paragraph(vec![
+                span("Hola ", false, false),
+                span("mundo", false, true),
+                span(" y ", false, false),
+                span("todos", true, false),
+                span(".", false, false),
+            ]),
+            Block::SceneBreak,
+            Block::ListItem(0, vec![span("Uno", false, false)]),
+            Block::ListItem(0, vec![span("Dos", false, false)]),
+            Block::ListItem(1, vec![span("Dos punto uno", false, false)]),
+        ]);
+    }
+
+    #[test]
+    fn definition_lists_and_hidden_content() {
+        let chapter = "<html><body><dl><dt>Término</dt><dd>Definición<span hidden=\"\">oculta</span></dd></dl>\
+            <p>H<sub>2</sub>O</p></body></html>";
+        let blocks = render_xhtml_to_blocks(chapter);
+        assert_eq!(blocks, vec![
+            Block::Paragraph(vec![span("Término", true, false)]),
+            Block::Definition(vec![span("Definición", false, false)]),
+            Block::Paragraph(vec![span("H\u{2082}O", false, false)]),
+        ]);
+        assert_eq!(blocks[2].plain_text(), "H\u{2082}O");
+    }
+
+    #[test]
+    fn blocks_match_the_text_renderer() {
+        let mut class_styles = ClassStyles::default();
+        class_styles.add_stylesheet(".it { font-style: italic }");
+        let chapter = "<html><body><p>Dijo <q>hola</q> al <span class=\"it\">barco</span>.<a href=\"#n1\">1</a></p>\
+            <aside epub:type=\"footnote\" id=\"n1\"><p>La nota</p></aside>\
+            <p>Verso uno<br/>verso dos</p>\
+            <figure><img src=\"mapa.png\" alt=\"Mapa\"/><figcaption>Ruta</figcaption></figure></body></html>";
+        let blocks = render_xhtml_to_blocks_with(chapter, &RenderOptions::default(), &class_styles);
+        assert_eq!(blocks, vec![
+            Block::Paragraph(vec![
+                span("Dijo \u{201c}hola\u{201d} al ", false, false),
+                span("barco", false, true),
+                span(".1", false, false),
+            ]),
+            Block::Paragraph(vec![span("Verso uno", false, false)]),
+            Block::Paragraph(vec![span("verso dos", false, false)]),
+            Block::Paragraph(vec![span("[Imagen: Mapa]", false, false)]),
+            Block::Paragraph(vec![span("Figura: Ruta", false, false)]),
+        ]);
+    }
+}
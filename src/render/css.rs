@@ -0,0 +1,176 @@
+// src/render/css.rs
+// CSS mínimo para recuperar el énfasis: algunos libros marcan la negrita y la cursiva
+// solo con clases (<span class="it">). Se leen las reglas de selectores de clase simples
+// (".it", "span.it") y se ignora todo lo demás
+use std::collections::{HashMap, HashSet};
+
+use scraper::{Html, Selector};
+
+use crate::path_utils;
+
+// Clases cuyas reglas ponen el texto en negrita o en cursiva
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClassStyles {
+    pub bold: HashSet<String>,
+    pub italic: HashSet<String>,
+}
+
+impl ClassStyles {
+    // Lee las reglas de una hoja de estilos y las añade a las ya conocidas
+    pub fn add_stylesheet(&mut self, css: &str) {
+        let css = strip_comments(css);
+        let mut rest = css.as_str();
+        while let Some(open) = rest.find('{') {
+            let prelude = rest[..open].trim();
+            let Some(close) = matching_brace(&rest[open..]) else {
+                break;
+            };
+            let body = &rest[open + 1..open + close];
+            rest = &rest[open + close + 1..];
+
+            // Reglas @ (@media, @font-face...): se ignoran enteras
+            let prelude = prelude.rsplit(';').next().unwrap_or("").trim();
+            if prelude.starts_with('@') {
+                continue;
+            }
+            let (bold, italic) = emphasis(body);
+            if !bold && !italic {
+                continue;
+            }
+            for class in prelude.split(',').filter_map(simple_class) {
+                if bold {
+                    self.bold.insert(class.to_string());
+                }
+                if italic {
+                    self.italic.insert(class.to_string());
+                }
+            }
+        }
+    }
+
+    // (negrita, cursiva) que dan las clases del elemento
+    pub fn emphasis<'a>(&self, mut classes: impl Iterator<Item = &'a str> + Clone) -> (bool, bool) {
+        let bold = classes.clone().any(|class| self.bold.contains(class));
+        let italic = classes.any(|class| self.italic.contains(class));
+        (bold, italic)
+    }
+}
+
+// Clases con negrita/cursiva según las hojas de estilo enlazadas y los <style> del
+// capítulo. `read` lee una hoja por su ruta completa en el ZIP; las hojas ya leídas se
+// guardan en `cache` y las que no se pueden leer se ignoran
+pub fn chapter_class_styles(
+    chapter_href: &str,
+    content: &str,
+    cache: &mut HashMap<String, String>,
+    mut read: impl FnMut(&str) -> Option<Vec<u8>>,
+) -> ClassStyles {
+    let (hrefs, inline) = stylesheets(content);
+    let mut class_styles = ClassStyles::default();
+    for href in hrefs {
+        let path = path_utils::resolve(chapter_href, &href);
+        let css = cache.entry(path).or_insert_with_key(|path| {
+            read(path).map(|bytes| String::from_utf8_lossy(&bytes).into_owned()).unwrap_or_default()
+        });
+        class_styles.add_stylesheet(css);
+    }
+    class_styles.add_stylesheet(&inline);
+    class_styles
+}
+
+// Hojas de estilo enlazadas (<link rel="stylesheet" href>) y contenido de los <style>
+// de un capítulo
+fn stylesheets(xhtml_content: &str) -> (Vec<String>, String) {
+    let document = Html::parse_document(xhtml_content);
+    let link_selector = Selector::parse("link[href]").unwrap();
+    let hrefs = document.select(&link_selector)
+        .filter(|link| {
+            link.value().attr("rel")
+                .is_some_and(|rel| rel.split_whitespace().any(|rel| rel.eq_ignore_ascii_case("stylesheet")))
+        })
+        .filter_map(|link| link.value().attr("href"))
+        .map(str::to_string)
+        .collect();
+    let style_selector = Selector::parse("style").unwrap();
+    let inline = document.select(&style_selector)
+        .flat_map(|style| style.text())
+        .collect();
+    (hrefs, inline)
+}
+
+fn strip_comments(css: &str) -> String {
+    let mut result = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        result.push_str(&rest[..start]);
+        rest = rest[start + 2..].find("*/").map_or("", |end| &rest[start + 2 + end + 2..]);
+    }
+    result.push_str(rest);
+    result
+}
+
+// Posición de la llave que cierra la que abre el texto (que empieza por '{')
+fn matching_brace(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (index, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// Clase de un selector simple (".it" o "span.it"); None para cualquier otro selector
+fn simple_class(selector: &str) -> Option<&str> {
+    let (element, class) = selector.trim().split_once('.')?;
+    let is_name = |name: &str| name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    (is_name(element) && !class.is_empty() && is_name(class)).then_some(class)
+}
+
+// (negrita, cursiva) que declaran las propiedades de una regla
+fn emphasis(body: &str) -> (bool, bool) {
+    let mut bold = false;
+    let mut italic = false;
+    for declaration in body.split(';') {
+        let Some((property, value)) = declaration.split_once(':') else {
+            continue;
+        };
+        let value = value.trim().trim_end_matches("!important").trim().to_ascii_lowercase();
+        match property.trim().to_ascii_lowercase().as_str() {
+            "font-weight" => {
+                bold = value == "bold" || value == "bolder" || value.parse::<u32>().is_ok_and(|weight| weight >= 600);
+            }
+            "font-style" => italic = value == "italic" || value == "oblique",
+            _ => {}
+        }
+    }
+    (bold, italic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_simple_class_rules_only() {
+        let mut styles = ClassStyles::default();
+        styles.add_stylesheet("/* títulos */ @charset \"utf-8\";\n\
+            .it { font-style: italic }\n\
+            span.bd, .strong { font-weight: 700 !important; }\n\
+            p .nested { font-style: italic }\n\
+            .light { font-weight: 300 }\n\
+            @media screen { .media { font-weight: bold } }");
+        assert!(styles.italic.contains("it"));
+        assert!(styles.bold.contains("bd") && styles.bold.contains("strong"));
+        assert!(!styles.italic.contains("nested"));
+        assert!(!styles.bold.contains("light") && !styles.bold.contains("media"));
+        assert_eq!(styles.emphasis(["x", "it"].into_iter()), (false, true));
+    }
+}
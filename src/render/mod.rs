@@ -1,116 +1,716 @@
 // src/render/mod.rs
+use std::borrow::Cow;
+use std::collections::HashMap;
 use scraper::{Html, Selector, Node, ElementRef};
 use std::fmt::Write; // Para escribir en String
 
+// Separador que sustituye a <hr>; la UI lo centra al conocer el ancho disponible
+pub const SCENE_BREAK: &str = "* * *";
+
+mod blocks;
+mod css;
+#[allow(unused_imports)]
+pub use blocks::{render_xhtml_to_blocks, render_xhtml_to_blocks_with, Block, TextSpan};
+pub use css::{chapter_class_styles, ClassStyles};
+
+// Sangría de la primera línea de cada párrafo con ParagraphStyle::Indent. La UI la
+// reconoce para justificar el resto de la línea
+pub const PARAGRAPH_INDENT: &str = "   ";
+
+// Caracteres invisibles que se eliminan del texto: guion blando, espacio de anchura cero,
+// unión de palabras y BOM. Solo indican dónde se puede (o no) cortar una línea, algo que
+// el ajuste del lector no usa, y algunos terminales los dibujan o les dan ancho. Los
+// ZWJ/ZWNJ se conservan porque cambian cómo se escribe el texto (emoji, persa...)
+const INVISIBLE_CHARS: &[char] = &['\u{ad}', '\u{200b}', '\u{2060}', '\u{feff}'];
+
+// Sangría de las definiciones (<dd>) bajo su término
+const DEFINITION_INDENT: &str = "    ";
+
+// Sangría añadida por cada nivel de lista anidada, antes del marcador
+const LIST_INDENT: &str = "  ";
+
+// Cómo se marcan los encabezados (h1-h6) en el texto
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeadingStyle {
+    Hash,  // "# Título" (estilo Markdown)
+    Plain, // Sin prefijo
+}
+
+// Cómo se marcan los elementos de lista
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListStyle {
+    Dash,   // "  - elemento"
+    Bullet, // "  • elemento"
+}
+
+impl ListStyle {
+    // Prefijo que se escribe antes de cada <li>
+    pub fn prefix(self) -> &'static str {
+        match self {
+            ListStyle::Dash => "  - ",
+            ListStyle::Bullet => "  \u{2022} ",
+        }
+    }
+}
+
+// Cómo se separan los párrafos
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParagraphStyle {
+    Spaced, // Una línea en blanco entre párrafos (estilo web)
+    Indent, // Sangría de primera línea y sin línea en blanco (estilo libro impreso)
+}
+
+// Comillas con las que se rodean las citas en línea (<q>)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuoteStyle {
+    Curly,      // “cita” y ‘cita anidada’
+    Guillemets, // «cita» y ‹cita anidada›
+}
+
+impl QuoteStyle {
+    // Comillas habituales para el idioma del libro (código BCP 47, p. ej. "es-ES")
+    pub fn for_language(language: Option<&str>) -> Self {
+        let primary = language.unwrap_or("").trim().split(['-', '_']).next().unwrap_or("").to_lowercase();
+        match primary.as_str() {
+            "es" | "fr" => QuoteStyle::Guillemets,
+            _ => QuoteStyle::Curly,
+        }
+    }
+
+    // Comillas de apertura y cierre según la profundidad de anidamiento (0 = exterior);
+    // las citas dentro de otra alternan a las simples
+    fn marks(self, depth: usize) -> (&'static str, &'static str) {
+        match (self, depth % 2) {
+            (QuoteStyle::Curly, 0) => ("\u{201c}", "\u{201d}"),
+            (QuoteStyle::Curly, _) => ("\u{2018}", "\u{2019}"),
+            (QuoteStyle::Guillemets, 0) => ("\u{ab}", "\u{bb}"),
+            (QuoteStyle::Guillemets, _) => ("\u{2039}", "\u{203a}"),
+        }
+    }
+}
+
+// Etiquetas que preceden a los pies de figura (<figcaption>) y de tabla (<caption>)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaptionLabels {
+    pub figure: &'static str,
+    pub table: &'static str,
+}
+
+impl CaptionLabels {
+    // Etiquetas en el idioma del libro; en español si no hay traducción
+    pub fn for_language(language: Option<&str>) -> Self {
+        let primary = language.unwrap_or("").trim().split(['-', '_']).next().unwrap_or("").to_lowercase();
+        match primary.as_str() {
+            "en" => CaptionLabels { figure: "Figure:", table: "Table:" },
+            "fr" => CaptionLabels { figure: "Figure :", table: "Tableau :" },
+            _ => CaptionLabels::default(),
+        }
+    }
+}
+
+impl Default for CaptionLabels {
+    fn default() -> Self {
+        CaptionLabels { figure: "Figura:", table: "Tabla:" }
+    }
+}
+
+// Palabra del marcador de imagen ("[Imagen: alt]") en el idioma del libro; en español
+// si no hay traducción
+pub fn image_label_for_language(language: Option<&str>) -> &'static str {
+    let primary = language.unwrap_or("").trim().split(['-', '_']).next().unwrap_or("").to_lowercase();
+    match primary.as_str() {
+        "en" | "fr" => "Image",
+        _ => "Imagen",
+    }
+}
+
+// Opciones que controlan cómo se convierte el XHTML a texto
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderOptions {
+    // Añadir marcadores estilo Markdown (*cursiva*, **negrita**) para el énfasis
+    pub emphasis_markers: bool,
+    pub heading_style: HeadingStyle,
+    pub list_style: ListStyle,
+    pub paragraph_style: ParagraphStyle,
+    // Mostrar el destino de los enlaces tras su texto: "texto [href]"
+    pub keep_links: bool,
+    // Numerar los encabezados como un esquema (1, 1.1, 1.2, 2...) dentro de cada capítulo
+    pub numbered_headings: bool,
+    pub quote_style: QuoteStyle,
+    pub caption_labels: CaptionLabels,
+    pub image_label: &'static str,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            emphasis_markers: true,
+            heading_style: HeadingStyle::Hash,
+            list_style: ListStyle::Dash,
+            paragraph_style: ParagraphStyle::Spaced,
+            keep_links: false,
+            numbered_headings: false,
+            quote_style: QuoteStyle::Curly,
+            caption_labels: CaptionLabels::default(),
+            image_label: "Imagen",
+        }
+    }
+}
+
 // Parsea el contenido XHTML y lo convierte a texto plano formateado básico
+#[allow(dead_code)]
 pub fn render_xhtml_to_text(xhtml_content: &str) -> String {
+    render_xhtml_to_text_with(xhtml_content, &RenderOptions::default())
+}
+
+// Igual que render_xhtml_to_text, pero con opciones de renderizado explícitas
+pub fn render_xhtml_to_text_with(xhtml_content: &str, options: &RenderOptions) -> String {
+    render_chapter(xhtml_content, options).text
+}
+
+// Un enlace del capítulo renderizado
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkRef {
+    pub line: usize,  // Línea del texto renderizado (antes de ajustar al ancho)
+    pub text: String, // Texto del enlace tal como se muestra
+    pub href: String, // Destino tal como aparece en el XHTML (relativo al capítulo)
+}
+
+// Una imagen (<img>) del capítulo renderizado, marcada en el texto con "[Imagen...]"
+// (o la etiqueta de RenderOptions::image_label)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageRef {
+    pub line: usize,  // Línea del texto renderizado en la que está el marcador
+    pub src: String,  // Origen tal como aparece en el XHTML (relativo al capítulo)
+}
+
+// Estilo de un tramo de texto, para que la UI lo muestre con atributos del terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextStyle {
+    Bold,
+    Italic,
+    Heading(u8), // Nivel (1-6)
+    Verse, // Línea cortada con <br> (poesía): la UI no la justifica
+}
+
+// Tramo con estilo dentro de una línea del texto renderizado. La vista de capítulo aplica
+// estos tramos sobre el mismo texto plano que usan --dump-text, la búsqueda y la
+// justificación, en vez de construirse a partir de bloques estructurados
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StyleRange {
+    pub line: usize,  // Línea del texto renderizado (antes de ajustar al ancho)
+    pub start: usize, // Bytes dentro de la línea: [start, end)
+    pub end: usize,
+    pub style: TextStyle,
+}
+
+// Texto renderizado junto con sus enlaces y anclas, para poder seguir notas al pie
+#[derive(Debug, Clone, Default)]
+pub struct RenderedChapter {
+    pub text: String,
+    pub links: Vec<LinkRef>,
+    // id de elemento -> línea del texto renderizado en la que empieza
+    pub anchors: HashMap<String, usize>,
+    // Énfasis y encabezados, ordenados por posición
+    pub styles: Vec<StyleRange>,
+    // Notas al pie (epub:type="footnote") por id, separadas del texto principal
+    pub footnotes: HashMap<String, String>,
+    pub images: Vec<ImageRef>,
+    // Dirección declarada con dir en <body> o <html>: Some(true) si es "rtl", Some(false)
+    // si es "ltr" y None si el documento no la indica
+    pub rtl: Option<bool>,
+    // Título propio del archivo (ver document_title)
+    pub title: Option<String>,
+}
+
+// Enlaces, anclas y estilos encontrados al recorrer el documento, con su posición en
+// bytes dentro de la salida
+#[derive(Default)]
+struct Targets {
+    links: Vec<(usize, String, String)>,
+    anchors: Vec<(String, usize)>,
+    styles: Vec<(usize, usize, TextStyle)>,
+    footnotes: Vec<(String, String)>,
+    images: Vec<(usize, String)>,
+    heading_counters: [usize; 6], // Encabezados vistos por nivel (h1-h6) para numerarlos
+    quote_depth: usize,           // <q> abiertos, para alternar comillas dobles y simples
+    class_styles: ClassStyles,    // Clases que el CSS del capítulo pone en negrita o cursiva
+    after_break: bool,            // La línea en curso empezó tras un <br>
+    list_depth: usize,            // Listas <ul>/<ol> abiertas
+}
+
+impl Targets {
+    // Número de esquema del siguiente encabezado del nivel dado (1-6): cuenta uno más en su
+    // nivel y reinicia los inferiores. Los niveles superiores sin encabezados se omiten,
+    // así que un capítulo que empieza por <h2> se numera 1, 1.1, 2...
+    fn heading_number(&mut self, level: usize) -> String {
+        let level = level.clamp(1, 6);
+        self.heading_counters[level - 1] += 1;
+        self.heading_counters[level..].iter_mut().for_each(|counter| *counter = 0);
+        self.heading_counters[..level].iter()
+            .skip_while(|&&counter| counter == 0)
+            .map(|counter| counter.to_string())
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+
+    // Marca como verso la línea en curso de la salida, de su inicio hasta `end`
+    fn mark_verse(&mut self, output: &str, end: usize) {
+        let start = output[..end].rfind('\n').map_or(0, |newline| newline + 1);
+        self.styles.push((start, end, TextStyle::Verse));
+    }
+
+    // Al terminar la línea que siguió a un <br>, la marca también como verso
+    fn close_verse(&mut self, output: &str) {
+        if std::mem::take(&mut self.after_break) && !output.ends_with('\n') {
+            self.mark_verse(output, output.len());
+        }
+    }
+
+    fn mark(&self) -> (usize, usize, usize, usize) {
+        (self.links.len(), self.anchors.len(), self.styles.len(), self.images.len())
+    }
+
+    // Los elementos renderizados en un búfer auxiliar (<sup>, <ruby>) se recolocan
+    // en la posición de la salida principal donde se insertará ese búfer. Los estilos
+    // quedan vacíos: el texto del búfer se transforma y sus posiciones ya no valen
+    fn move_since(&mut self, (links, anchors, styles, images): (usize, usize, usize, usize), position: usize) {
+        self.links[links..].iter_mut().for_each(|link| link.0 = position);
+        self.images[images..].iter_mut().for_each(|image| image.0 = position);
+        self.anchors[anchors..].iter_mut().for_each(|anchor| anchor.1 = position);
+        self.styles[styles..].iter_mut().for_each(|style| (style.0, style.1) = (position, position));
+    }
+}
+
+// Procesa los hijos del elemento con la negrita y/o cursiva indicadas, con los mismos
+// marcadores y estilos que <strong> y <em>
+fn process_emphasized(node: ElementRef, output: &mut String, options: &RenderOptions, targets: &mut Targets, bold: bool, italic: bool) {
+    let bold_marker = if bold && options.emphasis_markers { "**" } else { "" };
+    let italic_marker = if italic && options.emphasis_markers { "*" } else { "" };
+    write!(output, "{}{}", bold_marker, italic_marker).ok();
+    let start = output.len();
+    process_node(node, output, options, targets);
+    if bold {
+        targets.styles.push((start, output.len(), TextStyle::Bold));
+    }
+    if italic {
+        targets.styles.push((start, output.len(), TextStyle::Italic));
+    }
+    write!(output, "{}{}", italic_marker, bold_marker).ok();
+}
+
+// Renderiza el capítulo y localiza sus enlaces y anclas en el texto resultante
+pub fn render_chapter(xhtml_content: &str, options: &RenderOptions) -> RenderedChapter {
+    render_chapter_with_css(xhtml_content, options, &ClassStyles::default())
+}
+
+// render_chapter aplicando además el énfasis que el CSS del capítulo da a sus clases
+pub fn render_chapter_with_css(xhtml_content: &str, options: &RenderOptions, class_styles: &ClassStyles) -> RenderedChapter {
     let document = Html::parse_document(xhtml_content);
     let mut output = String::new();
+    let mut targets = Targets { class_styles: class_styles.clone(), ..Targets::default() };
     // Procesamos el body, o todo el documento si no hay body
     let body_selector = Selector::parse("body").unwrap();
     // Select the body element if it exists, otherwise use the document's root element
-    let root_node = document.select(&body_selector).next().unwrap_or_else(|| document.root_element());
+    let body = document.select(&body_selector).next();
+    let root_node = body.unwrap_or_else(|| document.root_element());
+    let rtl = [body, Some(document.root_element())]
+        .into_iter()
+        .flatten()
+        .find_map(|element| element.value().attr("dir"))
+        .map(|dir| dir.trim().eq_ignore_ascii_case("rtl"));
 
-    process_node(root_node, &mut output, 0);
+    process_node(root_node, &mut output, options, &mut targets);
 
-    // Limpieza simple: reduce múltiples saltos de línea a un máximo de dos
-    let lines: Vec<&str> = output.lines().collect();
+    // Limpieza simple: reduce múltiples saltos de línea a un máximo de dos.
+    // line_map guarda a qué línea limpia corresponde cada línea original
     let mut cleaned_output = String::new();
+    let mut line_map = Vec::new();
+    let mut cleaned_lines: usize = 0;
     let mut consecutive_empty_lines = 0;
-    for line in lines {
+    for line in output.lines() {
+        line_map.push(cleaned_lines);
         let trimmed_line = line.trim();
         if trimmed_line.is_empty() {
             consecutive_empty_lines += 1;
             if consecutive_empty_lines <= 2 {
                 writeln!(cleaned_output).ok();
+                cleaned_lines += 1;
             }
         } else {
             consecutive_empty_lines = 0;
-            writeln!(cleaned_output, "{}", line).ok(); // Preserva sangría si existe
+            writeln!(cleaned_output, "{}", line.trim_end()).ok(); // Preserva sangría si existe
+            cleaned_lines += 1;
         }
     }
 
-    cleaned_output.trim().to_string() // Elimina espacios/saltos al inicio/final
+    // Las líneas vacías del principio desaparecen con el trim final
+    let leading_empty = cleaned_output.lines().take_while(|line| line.trim().is_empty()).count();
+    let newlines: Vec<usize> = output.match_indices('\n').map(|(index, _)| index).collect();
+    let line_at = |position: usize| {
+        let raw_line = newlines.partition_point(|&newline| newline < position);
+        line_map.get(raw_line).copied().unwrap_or(cleaned_lines).saturating_sub(leading_empty)
+    };
+
+    // Los estilos se parten por líneas y se expresan en bytes dentro de cada línea limpia.
+    // Solo se quitan las líneas vacías del principio: la sangría de la primera línea se
+    // conserva, igual que la de las demás
+    let text = cleaned_output.trim_start_matches('\n').trim_end();
+    let clean_lines: Vec<&str> = text.lines().collect();
+    let mut styles = Vec::new();
+    for &(start, end, style) in &targets.styles {
+        let mut position = start;
+        while position < end {
+            let raw_line = newlines.partition_point(|&newline| newline < position);
+            let line_start = if raw_line == 0 { 0 } else { newlines[raw_line - 1] + 1 };
+            let line_end = newlines.get(raw_line).copied().unwrap_or(output.len()).min(end);
+            let line = line_at(position);
+            let line_len = clean_lines.get(line).map_or(0, |line| line.len());
+            let range_start = (position - line_start).min(line_len);
+            let range_end = (line_end - line_start).min(line_len);
+            if range_start < range_end {
+                styles.push(StyleRange { line, start: range_start, end: range_end, style });
+            }
+            position = line_end + 1;
+        }
+    }
+    styles.sort_by_key(|range| (range.line, range.start));
+
+    RenderedChapter {
+        text: text.to_string(), // Sin espacios/saltos al inicio/final
+        links: targets.links.into_iter()
+            .map(|(position, text, href)| LinkRef { line: line_at(position), text, href })
+            .collect(),
+        anchors: targets.anchors.into_iter()
+            .map(|(id, position)| (id, line_at(position)))
+            .collect(),
+        styles,
+        footnotes: targets.footnotes.into_iter().collect(),
+        images: targets.images.into_iter()
+            .map(|(position, src)| ImageRef { line: line_at(position), src })
+            .collect(),
+        rtl,
+        title: document_title(&document),
+    }
+}
+
+// Quita del texto los caracteres de INVISIBLE_CHARS (sin copiarlo si no tiene ninguno)
+fn strip_invisible(text: &str) -> Cow<'_, str> {
+    match text.contains(INVISIBLE_CHARS) {
+        true => Cow::Owned(text.replace(INVISIBLE_CHARS, "")),
+        false => Cow::Borrowed(text),
+    }
+}
+
+// Título propio del archivo (<head><title>), sin espacios sobrantes. None si no tiene
+// o está vacío
+fn document_title(document: &Html) -> Option<String> {
+    let title_selector = Selector::parse("head > title").unwrap();
+    let title = document.select(&title_selector).next()?
+        .text()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let title = strip_invisible(&title).split_whitespace().collect::<Vec<_>>().join(" ");
+    (!title.is_empty()).then_some(title)
 }
 
 // Función recursiva para procesar nodos HTML
-fn process_node(node: ElementRef, output: &mut String, depth: usize) {
+fn process_node(node: ElementRef, output: &mut String, options: &RenderOptions, targets: &mut Targets) {
     for child in node.children() {
         match child.value() {
             Node::Text(text) => {
-                // Reemplaza múltiples espacios/saltos de línea dentro del texto con uno solo
-                let cleaned_text = text.text.split_whitespace().collect::<Vec<_>>().join(" ");
+                // El parser ya decodifica las entidades (&amp;, &#8217;...). &nbsp; llega como
+                // U+00A0, que split_whitespace trata como espacio, así que se convierte en un
+                // espacio normal y la justificación puede repartirlo
+                let raw = strip_invisible(&text.text);
+                let cleaned_text = raw.split_whitespace().collect::<Vec<_>>().join(" ");
+                // Conservar un espacio en los bordes para no pegar el texto a <em>, <a>...
+                if raw.starts_with(char::is_whitespace) && !output.is_empty() && !output.ends_with(char::is_whitespace) {
+                    write!(output, " ").ok();
+                }
                 if !cleaned_text.is_empty() {
                     write!(output, "{}", cleaned_text).ok();
+                    if raw.ends_with(char::is_whitespace) {
+                        write!(output, " ").ok();
+                    }
+                }
+            }
+            Node::Element(element) if is_footnote(element) => {
+                // Nota al pie EPUB 3: no se muestra en el texto, la UI la abre en una ventana
+                // emergente al seguir su llamada. Suelen estar ocultas, así que va antes de is_hidden
+                if let (Some(id), Some(element_ref)) = (element.id(), ElementRef::wrap(child)) {
+                    let mut note = String::new();
+                    process_node(element_ref, &mut note, options, &mut Targets::default());
+                    let paragraphs: Vec<&str> = note.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+                    targets.footnotes.push((id.to_string(), paragraphs.join("\n")));
                 }
             }
+            Node::Element(element) if is_hidden(element) => {
+                // Contenido oculto (notas emergentes, etiquetas de accesibilidad): no se muestra
+            }
             Node::Element(element) => {
                 let tag_name = element.name().to_lowercase();
-                let needs_leading_newline = matches!(tag_name.as_str(), "p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "li" | "div" | "br");
-                let needs_trailing_newline = matches!(tag_name.as_str(), "p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "div" | "br");
+                let needs_leading_newline = matches!(tag_name.as_str(), "p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "li" | "div" | "br" | "figure" | "figcaption" | "table" | "caption" | "dl" | "dt" | "dd");
+                let needs_trailing_newline = matches!(tag_name.as_str(), "p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "div" | "br" | "figure" | "figcaption" | "table" | "caption" | "dl" | "dt" | "dd");
                 let is_block = needs_leading_newline || needs_trailing_newline;
 
+                if is_block && tag_name != "br" {
+                    targets.close_verse(output);
+                }
+
                 // Añadir salto de línea antes de elementos de bloque si no estamos al principio
                 if needs_leading_newline && !output.is_empty() && !output.ends_with('\n') {
                     writeln!(output).ok();
                 }
 
+                // Destino de enlaces con fragmento (#id)
+                if let Some(id) = element.id() {
+                    targets.anchors.push((id.to_string(), output.len()));
+                }
+
                 // Procesamiento específico por etiqueta
                 match tag_name.as_str() {
                     "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
-                        write!(output, "# ").ok(); // Estilo Markdown simple
+                        if options.heading_style == HeadingStyle::Hash {
+                            write!(output, "# ").ok(); // Estilo Markdown simple
+                        }
+                        let start = output.len();
+                        let level = tag_name[1..].parse().unwrap_or(1);
+                        if options.numbered_headings {
+                            write!(output, "{} ", targets.heading_number(level as usize)).ok();
+                        }
                         if let Some(element_ref) = ElementRef::wrap(child) {
-                            process_node(element_ref, output, depth + 1);
+                            process_node(element_ref, output, options, targets);
                         }
+                        targets.styles.push((start, output.len(), TextStyle::Heading(level)));
                         writeln!(output).ok(); // Salto de línea extra después de encabezado
                     }
                     "p" => {
+                        if options.paragraph_style == ParagraphStyle::Indent && (output.is_empty() || output.ends_with('\n')) {
+                            write!(output, "{}", PARAGRAPH_INDENT).ok();
+                        }
                         if let Some(element_ref) = ElementRef::wrap(child) {
-                            process_node(element_ref, output, depth + 1);
+                            let (bold, italic) = targets.class_styles.emphasis(element.classes());
+                            process_emphasized(element_ref, output, options, targets, bold, italic);
                         }
                     }
                     "li" => {
-                        write!(output, "  - ").ok(); // Sangría y guion para listas
+                        // Sangría y marcador para listas, más sangría por cada nivel de anidamiento
+                        let nesting = LIST_INDENT.repeat(targets.list_depth.saturating_sub(1));
+                        write!(output, "{}{}", nesting, options.list_style.prefix()).ok();
                         if let Some(element_ref) = ElementRef::wrap(child) {
-                            process_node(element_ref, output, depth + 1);
+                            process_node(element_ref, output, options, targets);
+                        }
+                    }
+                    "ul" | "ol" => {
+                        targets.list_depth += 1;
+                        if let Some(element_ref) = ElementRef::wrap(child) {
+                            process_node(element_ref, output, options, targets);
+                        }
+                        targets.list_depth -= 1;
+                    }
+                    "sup" | "sub" => {
+                        // Índices (llamadas a notas, fórmulas): Unicode si todos los caracteres
+                        // tienen equivalente, y si no notación ^{…} / _{…}
+                        let mut inner = String::new();
+                        let first = targets.mark();
+                        if let Some(element_ref) = ElementRef::wrap(child) {
+                            process_node(element_ref, &mut inner, options, targets);
+                        }
+                        // Las llamadas a notas suelen ir dentro de <sup>: sus enlaces quedan aquí
+                        targets.move_since(first, output.len());
+                        let inner = inner.trim();
+                        if !inner.is_empty() {
+                            let superscript = tag_name == "sup";
+                            let mapper = if superscript { to_superscript } else { to_subscript };
+                            match inner.chars().map(mapper).collect::<Option<String>>() {
+                                Some(mapped) => write!(output, "{}", mapped).ok(),
+                                None if superscript => write!(output, "^{{{}}}", inner).ok(),
+                                None => write!(output, "_{{{}}}", inner).ok(),
+                            };
+                        }
+                    }
+                    "dt" => {
+                        // Término de una lista de definiciones: una línea en blanco lo separa
+                        // de la definición anterior (varios <dt> seguidos quedan juntos)
+                        let after_definition = child.prev_siblings()
+                            .find_map(ElementRef::wrap)
+                            .is_some_and(|sibling| sibling.value().name().eq_ignore_ascii_case("dd"));
+                        if after_definition && !output.ends_with("\n\n") {
+                            writeln!(output).ok();
+                        }
+                        let marker = if options.emphasis_markers { "**" } else { "" };
+                        write!(output, "{}", marker).ok();
+                        let start = output.len();
+                        if let Some(element_ref) = ElementRef::wrap(child) {
+                            process_node(element_ref, output, options, targets);
+                        }
+                        targets.styles.push((start, output.len(), TextStyle::Bold));
+                        write!(output, "{}", marker).ok();
+                    }
+                    "dd" => {
+                        // Definición sangrada debajo de su término
+                        write!(output, "{}", DEFINITION_INDENT).ok();
+                        if let Some(element_ref) = ElementRef::wrap(child) {
+                            process_node(element_ref, output, options, targets);
                         }
                     }
                     "em" | "i" => {
-                        write!(output, "*").ok(); // Cursiva
+                        let marker = if options.emphasis_markers { "*" } else { "" }; // Cursiva
+                        write!(output, "{}", marker).ok();
+                        let start = output.len();
                         if let Some(element_ref) = ElementRef::wrap(child) {
-                            process_node(element_ref, output, depth + 1);
+                            process_node(element_ref, output, options, targets);
                         }
-                        write!(output, "*").ok();
+                        targets.styles.push((start, output.len(), TextStyle::Italic));
+                        write!(output, "{}", marker).ok();
+                    }
+                    "q" => {
+                        let (open, close) = options.quote_style.marks(targets.quote_depth);
+                        write!(output, "{}", open).ok();
+                        targets.quote_depth += 1;
+                        if let Some(element_ref) = ElementRef::wrap(child) {
+                            process_node(element_ref, output, options, targets);
+                        }
+                        targets.quote_depth -= 1;
+                        write!(output, "{}", close).ok();
                     }
                     "strong" | "b" => {
-                        write!(output, "**").ok(); // Negrita
+                        let marker = if options.emphasis_markers { "**" } else { "" }; // Negrita
+                        write!(output, "{}", marker).ok();
+                        let start = output.len();
+                        if let Some(element_ref) = ElementRef::wrap(child) {
+                            process_node(element_ref, output, options, targets);
+                        }
+                        targets.styles.push((start, output.len(), TextStyle::Bold));
+                        write!(output, "{}", marker).ok();
+                    }
+                    "a" => {
+                        let start = output.len();
                         if let Some(element_ref) = ElementRef::wrap(child) {
-                            process_node(element_ref, output, depth + 1);
+                            process_node(element_ref, output, options, targets);
+                        }
+                        if let Some(href) = element.attr("href").filter(|h| !h.is_empty()) {
+                            let text = output[start..].trim().to_string();
+                            targets.links.push((start, text, href.to_string()));
+                            // Las llamadas a notas del propio capítulo (#id) no se repiten
+                            if options.keep_links && !href.starts_with('#') {
+                                write!(output, " [{}]", href).ok();
+                            }
+                        }
+                    }
+                    "ruby" => {
+                        if let Some(ruby) = ElementRef::wrap(child) {
+                            render_ruby(ruby, output, options, targets);
+                        }
+                    }
+                    "rp" => {
+                        // Paréntesis de respaldo para lectores sin soporte de ruby
+                    }
+                    "hr" => {
+                        // Cambio de escena: separador en su propio párrafo
+                        if !output.is_empty() && !output.ends_with("\n\n") {
+                            writeln!(output).ok();
                         }
-                        write!(output, "**").ok();
+                        writeln!(output, "{}", SCENE_BREAK).ok();
+                        writeln!(output).ok();
                     }
                     "br" => {
-                        // Ya manejado por needs_leading/trailing_newline
+                        // El salto ya lo escribe needs_leading_newline. Las líneas a ambos lados
+                        // del <br> son versos: se marcan para que la UI no las justifique
+                        if output.ends_with('\n') {
+                            targets.mark_verse(output, output.len() - 1);
+                        }
+                        targets.after_break = true;
                     }
-                    "img" | "script" | "style" | "link" | "head" | "meta" => {
+                    "img" => {
+                        // Marcador para no perder la existencia de la imagen ni su texto alternativo
+                        if !output.is_empty() && !output.ends_with(char::is_whitespace) {
+                            write!(output, " ").ok();
+                        }
+                        if let Some(src) = element.attr("src").filter(|src| !src.is_empty()) {
+                            targets.images.push((output.len(), src.to_string()));
+                        }
+                        match element.attr("alt").map(str::trim).filter(|alt| !alt.is_empty()) {
+                            Some(alt) => write!(output, "[{}: {}] ", options.image_label, alt).ok(),
+                            None => write!(output, "[{}] ", options.image_label).ok(),
+                        };
+                    }
+                    "figure" => {
+                        // Primero el contenido (imágenes, etc.) y después los pies de figura
+                        if let Some(element_ref) = ElementRef::wrap(child) {
+                            process_node(element_ref, output, options, targets);
+                            let captions = element_ref.children()
+                                .filter_map(ElementRef::wrap)
+                                .filter(|e| e.value().name().eq_ignore_ascii_case("figcaption"));
+                            for caption in captions {
+                                if !output.is_empty() && !output.ends_with('\n') {
+                                    writeln!(output).ok();
+                                }
+                                write!(output, "{} ", options.caption_labels.figure).ok();
+                                process_node(caption, output, options, targets);
+                            }
+                        }
+                    }
+                    "table" => {
+                        // El título de la tabla va encima, pegado a su contenido
+                        if let Some(element_ref) = ElementRef::wrap(child) {
+                            let caption = element_ref.children()
+                                .filter_map(ElementRef::wrap)
+                                .find(|e| e.value().name().eq_ignore_ascii_case("caption"));
+                            if let Some(caption) = caption {
+                                write!(output, "{} ", options.caption_labels.table).ok();
+                                process_node(caption, output, options, targets);
+                                if !output.ends_with('\n') {
+                                    writeln!(output).ok();
+                                }
+                            }
+                            process_node(element_ref, output, options, targets);
+                        }
+                    }
+                    "caption" => {
+                        // Lo escribe la propia <table> antes de las filas
+                    }
+                    "figcaption" => {
+                        // Dentro de <figure> lo escribe el propio <figure>, debajo de la imagen
+                        let in_figure = child.parent()
+                            .and_then(ElementRef::wrap)
+                            .is_some_and(|parent| parent.value().name().eq_ignore_ascii_case("figure"));
+                        if !in_figure {
+                            if let Some(element_ref) = ElementRef::wrap(child) {
+                                process_node(element_ref, output, options, targets);
+                            }
+                        }
+                    }
+                    "script" | "style" | "link" | "head" | "meta" => {
                         // Ignorar estos elementos y su contenido
                     }
                     // Para otros elementos (div, span, etc.), procesa hijos directamente
                     _ => {
                         if let Some(element_ref) = ElementRef::wrap(child) {
-                            process_node(element_ref, output, depth + 1);
+                            // Las clases con énfasis solo se aplican a elementos en línea (<span>...):
+                            // los marcadores no pueden rodear saltos de línea
+                            let (bold, italic) = match is_block {
+                                true => (false, false),
+                                false => targets.class_styles.emphasis(element.classes()),
+                            };
+                            process_emphasized(element_ref, output, options, targets, bold, italic);
                         }
                     }
                 }
 
                 // Añadir salto de línea después de elementos de bloque
+                if needs_trailing_newline && tag_name != "br" {
+                    targets.close_verse(output);
+                }
                 if needs_trailing_newline {
                     // Asegúrate de que no haya ya un salto de línea
                     if !output.ends_with('\n') {
                         writeln!(output).ok();
                     }
                     // Añadir un salto extra después de párrafos para mejor separación
-                    if tag_name == "p" && !output.ends_with("\n\n") {
+                    let spaced = tag_name == "dl" || (tag_name == "p" && options.paragraph_style == ParagraphStyle::Spaced);
+                    if spaced && !output.ends_with("\n\n") {
                         writeln!(output).ok();
                     }
                 } else if is_block && !tag_name.is_empty() {
@@ -126,3 +726,280 @@ fn process_node(node: ElementRef, output: &mut String, depth: usize) {
         }
     }
 }
+
+// Furigana: "base(lectura)" para que la lectura quede junto a su base.
+// Admite <rb>/<rt> y texto suelto + <rt>; los <rp> se descartan
+fn render_ruby(ruby: ElementRef, output: &mut String, options: &RenderOptions, targets: &mut Targets) {
+    let mut base = String::new();
+    for part in ruby.children() {
+        match part.value() {
+            Node::Text(text) => base.push_str(text.text.trim()),
+            Node::Element(part_element) => match part_element.name() {
+                "rp" => {}
+                "rt" => {
+                    let reading = ElementRef::wrap(part)
+                        .map(|rt| rt.text().collect::<String>())
+                        .unwrap_or_default();
+                    write!(output, "{}({})", base, reading.trim()).ok();
+                    base.clear();
+                }
+                _ => {
+                    if let Some(element_ref) = ElementRef::wrap(part) {
+                        let first = targets.mark();
+                        process_node(element_ref, &mut base, options, targets);
+                        targets.move_since(first, output.len());
+                    }
+                }
+            },
+            _ => {}
+        }
+    }
+    output.push_str(&base);
+}
+
+// Cuerpo de una nota al pie marcado con epub:type="footnote" (y con id, para poder
+// enlazarlo). Las notas finales (endnote) se siguen mostrando en el texto
+fn is_footnote(element: &scraper::node::Element) -> bool {
+    element.id().is_some()
+        && element.attr("epub:type").is_some_and(|types| types.split_whitespace().any(|kind| kind == "footnote"))
+}
+
+// Elemento oculto por el propio documento: atributo hidden o un style en línea con
+// display:none / visibility:hidden. No se evalúa CSS más allá de esas declaraciones
+fn is_hidden(element: &scraper::node::Element) -> bool {
+    if element.attr("hidden").is_some() {
+        return true;
+    }
+    element.attr("style").is_some_and(|style| {
+        style.split(';').any(|declaration| {
+            let Some((property, value)) = declaration.split_once(':') else {
+                return false;
+            };
+            let property = property.trim().to_ascii_lowercase();
+            let value = value.trim().trim_end_matches("!important").trim().to_ascii_lowercase();
+            (property == "display" && value == "none") || (property == "visibility" && value == "hidden")
+        })
+    })
+}
+
+// Equivalente Unicode en superíndice de un carácter, si existe
+fn to_superscript(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '⁰', '1' => '¹', '2' => '²', '3' => '³', '4' => '⁴',
+        '5' => '⁵', '6' => '⁶', '7' => '⁷', '8' => '⁸', '9' => '⁹',
+        '+' => '⁺', '-' | '−' => '⁻', '=' => '⁼', '(' => '⁽', ')' => '⁾',
+        'n' => 'ⁿ', 'i' => 'ⁱ',
+        _ => return None,
+    })
+}
+
+// Equivalente Unicode en subíndice de un carácter, si existe
+fn to_subscript(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '₀', '1' => '₁', '2' => '₂', '3' => '₃', '4' => '₄',
+        '5' => '₅', '6' => '₆', '7' => '₇', '8' => '₈', '9' => '₉',
+        '+' => '₊', '-' | '−' => '₋', '=' => '₌', '(' => '₍', ')' => '₎',
+        'a' => 'ₐ', 'e' => 'ₑ', 'o' => 'ₒ', 'x' => 'ₓ', 'h' => 'ₕ', 'k' => 'ₖ',
+        'l' => 'ₗ', 'm' => 'ₘ', 'n' => 'ₙ', 'p' => 'ₚ', 's' => 'ₛ', 't' => 'ₜ',
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn css_classes_add_emphasis_to_inline_elements_and_paragraphs() {
+        let mut class_styles = ClassStyles::default();
+        class_styles.add_stylesheet(".it { font-style: italic } .bd { font-weight: bold }");
+        let chapter = "<html><body><p>Un <span class=\"it\">barco</span> y <span class=\"x bd\">otro</span></p>\
+            <p class=\"it\">Carta</p><div class=\"bd\"><p>Bloque</p></div></body></html>";
+        let rendered = render_chapter_with_css(chapter, &RenderOptions::default(), &class_styles);
+        assert_eq!(rendered.text, "Un *barco* y **otro**\n\n*Carta*\n\nBloque");
+        assert_eq!(render_chapter(chapter, &RenderOptions::default()).text, "Un barco y otro\n\nCarta\n\nBloque");
+    }
+
+    #[test]
+    fn records_image_sources_by_line() {
+        let chapter = "<html><body><p>Antes</p><p><img src=\"../Images/mapa.png\" alt=\"Mapa\"/></p></body></html>";
+        let rendered = render_chapter(chapter, &RenderOptions::default());
+        assert_eq!(rendered.text, "Antes\n\n[Imagen: Mapa]");
+        let options = RenderOptions { image_label: image_label_for_language(Some("en-GB")), ..RenderOptions::default() };
+        assert_eq!(render_xhtml_to_text_with(chapter, &options), "Antes\n\n[Image: Mapa]");
+        assert_eq!(rendered.images, vec![ImageRef { line: 2, src: "../Images/mapa.png".to_string() }]);
+    }
+
+    #[test]
+    fn inline_quotes_alternate_when_nested() {
+        let chapter = "<html><body><p>Dijo <q>vete <q>ya</q> de aquí</q>.</p></body></html>";
+        assert_eq!(render_xhtml_to_text(chapter), "Dijo \u{201c}vete \u{2018}ya\u{2019} de aquí\u{201d}.");
+        let options = RenderOptions { quote_style: QuoteStyle::for_language(Some("es-ES")), ..RenderOptions::default() };
+        assert_eq!(render_xhtml_to_text_with(chapter, &options), "Dijo \u{ab}vete \u{2039}ya\u{203a} de aquí\u{bb}.");
+        assert_eq!(QuoteStyle::for_language(Some("en")), QuoteStyle::Curly);
+    }
+
+    #[test]
+    fn captions_are_labeled_next_to_their_figure_or_table() {
+        let chapter = "<html><body><figure><img src=\"m.png\" alt=\"Mapa\"/><figcaption>Ruta del viaje</figcaption></figure>\
+            <table><caption>Distancias</caption><tr><td>10 km</td></tr></table></body></html>";
+        assert_eq!(render_xhtml_to_text(chapter), "[Imagen: Mapa]\nFigura: Ruta del viaje\nTabla: Distancias\n10 km");
+        let options = RenderOptions { caption_labels: CaptionLabels::for_language(Some("en-US")), ..RenderOptions::default() };
+        assert!(render_xhtml_to_text_with(chapter, &options).contains("Figure: Ruta del viaje"));
+    }
+
+    #[test]
+    fn decodes_named_and_numeric_entities() {
+        let chapter = "<html><body><p>Tom &amp; Jerry &lt;3 &#8217;tis &#x2014; fin&#46;</p></body></html>";
+        assert_eq!(render_xhtml_to_text(chapter), "Tom & Jerry <3 \u{2019}tis \u{2014} fin.");
+    }
+
+    #[test]
+    fn nbsp_becomes_regular_space() {
+        let chapter = "<html><body><p>10&nbsp;km&#160;al&nbsp;&nbsp;norte</p></body></html>";
+        let text = render_xhtml_to_text(chapter);
+        assert_eq!(text, "10 km al norte");
+        assert!(!text.contains('\u{a0}'));
+    }
+
+    #[test]
+    fn strips_soft_hyphens_and_zero_width_spaces() {
+        let chapter = "<html><body><p>extra&shy;ordi&#173;nario, auto-\u{200b}stop y ni\u{200d}ño</p></body></html>";
+        assert_eq!(render_xhtml_to_text(chapter), "extraordinario, auto-stop y ni\u{200d}ño");
+    }
+
+    #[test]
+    fn keeps_spaces_around_inline_elements() {
+        let chapter = "<html><body><p>Hola <em>mundo</em> y <a href=\"#n1\">nota</a>.</p></body></html>";
+        assert_eq!(render_xhtml_to_text(chapter), "Hola *mundo* y nota.");
+    }
+
+    #[test]
+    fn renders_sup_and_sub() {
+        let chapter = "<html><body><p>H<sub>2</sub>O y E=mc<sup>2</sup>, nota<sup><a href=\"#n1\">12</a></sup> y x<sup>ab</sup></p></body></html>";
+        assert_eq!(render_xhtml_to_text(chapter), "H\u{2082}O y E=mc\u{b2}, nota\u{b9}\u{b2} y x^{ab}");
+    }
+
+    #[test]
+    fn locates_links_and_anchors() {
+        let chapter = "<html><body><p>Intro</p>\n\n\n<p>Texto<sup><a href=\"#fn1\" id=\"ref1\">1</a></sup>.</p>\
+            <p id=\"fn1\">1. La nota <a href=\"#ref1\">volver</a></p></body></html>";
+        let rendered = render_chapter(chapter, &RenderOptions::default());
+        let lines: Vec<&str> = rendered.text.lines().collect();
+        assert_eq!(lines[rendered.anchors["fn1"]], "1. La nota volver");
+        assert_eq!(lines[rendered.anchors["ref1"]], "Texto\u{b9}.");
+        assert_eq!(rendered.links, vec![
+            LinkRef { line: 2, text: "1".to_string(), href: "#fn1".to_string() },
+            LinkRef { line: 4, text: "volver".to_string(), href: "#ref1".to_string() },
+        ]);
+    }
+
+    #[test]
+    fn records_emphasis_and_heading_ranges() {
+        let chapter = "<html><body><h1>Uno</h1><p>Hola <em>mundo</em> y <strong>todos</strong>.</p>\
+            <ul><li><b>Lista</b></li></ul></body></html>";
+        let options = RenderOptions { emphasis_markers: false, heading_style: HeadingStyle::Plain, ..RenderOptions::default() };
+        let rendered = render_chapter(chapter, &options);
+        let lines: Vec<&str> = rendered.text.lines().collect();
+        let styled: Vec<(&str, TextStyle)> = rendered.styles.iter()
+            .map(|range| (&lines[range.line][range.start..range.end], range.style))
+            .collect();
+        assert_eq!(styled, vec![
+            ("Uno", TextStyle::Heading(1)),
+            ("mundo", TextStyle::Italic),
+            ("todos", TextStyle::Bold),
+            ("Lista", TextStyle::Bold),
+        ]);
+    }
+
+    #[test]
+    fn list_style_sets_the_item_marker() {
+        let chapter = "<html><body><ul><li>Uno</li><li>Dos</li></ul></body></html>";
+        let options = RenderOptions { list_style: ListStyle::Bullet, ..RenderOptions::default() };
+        assert_eq!(render_xhtml_to_text_with(chapter, &options), "  \u{2022} Uno\n  \u{2022} Dos");
+    }
+
+    #[test]
+    fn keep_links_shows_link_targets_after_their_text() {
+        let chapter = "<html><body><p>Ver <a href=\"c2.xhtml#s1\">sección</a> y nota<a href=\"#n1\">1</a>.</p></body></html>";
+        assert_eq!(render_xhtml_to_text(chapter), "Ver sección y nota1.");
+        let options = RenderOptions { keep_links: true, ..RenderOptions::default() };
+        let rendered = render_chapter(chapter, &options);
+        assert_eq!(rendered.text, "Ver sección [c2.xhtml#s1] y nota1.");
+        assert_eq!(rendered.links[0].text, "sección");
+    }
+
+    #[test]
+    fn nested_list_items_are_indented_by_depth() {
+        let chapter = "<html><body><ul><li>Uno<ul><li>Uno.a<ol><li>Uno.a.i</li></ol></li></ul></li><li>Dos</li></ul></body></html>";
+        assert_eq!(render_xhtml_to_text(chapter), "  - Uno\n    - Uno.a\n      - Uno.a.i\n  - Dos");
+    }
+
+    #[test]
+    fn marks_lines_split_by_br_as_verse() {
+        let chapter = "<html><body><p>Verde que te quiero verde.<br/>Verde viento. <em>Verdes</em> ramas.</p>\
+            <p>Prosa sin cortes.</p></body></html>";
+        let rendered = render_chapter(chapter, &RenderOptions { emphasis_markers: false, ..RenderOptions::default() });
+        let verses: Vec<usize> = rendered.styles.iter()
+            .filter(|range| range.style == TextStyle::Verse)
+            .map(|range| range.line)
+            .collect();
+        assert_eq!(rendered.text, "Verde que te quiero verde.\nVerde viento. Verdes ramas.\n\nProsa sin cortes.");
+        assert_eq!(verses, vec![0, 1]);
+    }
+
+    #[test]
+    fn separates_footnotes_from_text() {
+        let chapter = "<html><body><p>Texto<a epub:type=\"noteref\" href=\"#n1\">1</a>.</p>\
+            <aside epub:type=\"footnote\" id=\"n1\" hidden=\"\"><p>La <em>nota</em>.</p><p>Sigue.</p></aside>\
+            <aside epub:type=\"footnote\"><p>Sin id</p></aside></body></html>";
+        let options = RenderOptions { emphasis_markers: false, ..RenderOptions::default() };
+        let rendered = render_chapter(chapter, &options);
+        assert_eq!(rendered.text, "Texto1.\n\nSin id");
+        assert_eq!(rendered.footnotes["n1"], "La nota.\nSigue.");
+        assert!(!rendered.anchors.contains_key("n1"));
+    }
+
+    #[test]
+    fn indent_style_indents_paragraphs_without_blank_lines() {
+        let chapter = "<html><body><h1>Uno</h1><p>Primero.</p><p>Segundo.</p><ul><li>Lista</li></ul></body></html>";
+        let options = RenderOptions { paragraph_style: ParagraphStyle::Indent, ..RenderOptions::default() };
+        assert_eq!(render_xhtml_to_text_with(chapter, &options), "# Uno\n   Primero.\n   Segundo.\n  - Lista");
+    }
+
+    #[test]
+    fn extracts_head_title() {
+        let title = |xhtml: &str| render_chapter(xhtml, &RenderOptions::default()).title;
+        let chapter = "<html><head><title>\n  Capítulo   3 </title></head><body><p>x</p></body></html>";
+        assert_eq!(title(chapter).as_deref(), Some("Capítulo 3"));
+        assert_eq!(title("<html><head><title> </title></head></html>"), None);
+        assert_eq!(title("<html><body><p>Sin título</p></body></html>"), None);
+    }
+
+    #[test]
+    fn reports_declared_direction() {
+        let options = RenderOptions::default();
+        assert_eq!(render_chapter(r#"<html dir="rtl"><body><p>שלום</p></body></html>"#, &options).rtl, Some(true));
+        assert_eq!(render_chapter(r#"<html dir="rtl"><body dir="ltr"><p>Hola</p></body></html>"#, &options).rtl, Some(false));
+        assert_eq!(render_chapter("<html><body><p>Hola</p></body></html>", &options).rtl, None);
+    }
+
+    #[test]
+    fn numbers_headings_as_an_outline() {
+        let chapter = "<html><body><h2>Uno</h2><h3>A</h3><h3>B</h3><h2>Dos</h2><h3>C</h3></body></html>";
+        let options = RenderOptions { numbered_headings: true, ..RenderOptions::default() };
+        let headings: Vec<String> = render_xhtml_to_text_with(chapter, &options).lines()
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect();
+        assert_eq!(headings, vec!["# 1 Uno", "# 1.1 A", "# 1.2 B", "# 2 Dos", "# 2.1 C"]);
+    }
+
+    #[test]
+    fn skips_hidden_elements() {
+        let chapter = "<html><body><p>Visible<span style=\"display: none\">oculto</span>.</p>\
+            <aside hidden=\"\"><p>Nota emergente</p></aside>\
+            <p style=\"visibility:hidden;\">Invisible</p><p style=\"display:block\">Fin</p></body></html>";
+        assert_eq!(render_xhtml_to_text(chapter), "Visible.\n\nFin");
+    }
+}
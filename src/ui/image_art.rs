@@ -0,0 +1,75 @@
+// src/ui/image_art.rs
+// Imágenes como arte de bloques para el terminal: cada celda muestra dos píxeles en
+// vertical con "▀" (el de arriba como color del texto y el de abajo como fondo)
+use image::RgbImage;
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+};
+
+const HALF_BLOCK: &str = "\u{2580}";
+
+// Decodifica la imagen (PNG, JPEG o GIF) y la reduce para que quepa en width x height
+// celdas conservando la proporción. None si el formato no se reconoce o está dañado
+pub fn render_half_blocks(bytes: &[u8], width: u16, height: u16) -> Option<Vec<Line<'static>>> {
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let image = image::load_from_memory(bytes).ok()?.to_rgb8();
+    let (image_width, image_height) = image.dimensions();
+    if image_width == 0 || image_height == 0 {
+        return None;
+    }
+
+    // Nunca se amplía: una imagen pequeña ocupa las celdas que le corresponden
+    let max_width = width as f64;
+    let max_height = height as f64 * 2.0;
+    let scale = (max_width / image_width as f64).min(max_height / image_height as f64).min(1.0);
+    let target_width = ((image_width as f64 * scale).round() as u32).max(1);
+    let target_height = ((image_height as f64 * scale).round() as u32).max(1);
+    let small = downscale(&image, target_width, target_height);
+
+    let lines = (0..target_height).step_by(2)
+        .map(|y| {
+            let spans: Vec<Span<'static>> = (0..target_width)
+                .map(|x| {
+                    let mut style = Style::default().fg(rgb(&small, x, y));
+                    if y + 1 < target_height {
+                        style = style.bg(rgb(&small, x, y + 1));
+                    }
+                    Span::styled(HALF_BLOCK, style)
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect();
+    Some(lines)
+}
+
+fn rgb(image: &RgbImage, x: u32, y: u32) -> Color {
+    let [r, g, b] = image.get_pixel(x, y).0;
+    Color::Rgb(r, g, b)
+}
+
+// Reduce la imagen promediando el bloque de píxeles que cae en cada píxel de destino,
+// lo que conserva mejor los colores que quedarse con un píxel suelto
+fn downscale(image: &RgbImage, width: u32, height: u32) -> RgbImage {
+    let (source_width, source_height) = image.dimensions();
+    RgbImage::from_fn(width, height, |x, y| {
+        let x0 = x * source_width / width;
+        let x1 = ((x + 1) * source_width / width).max(x0 + 1);
+        let y0 = y * source_height / height;
+        let y1 = ((y + 1) * source_height / height).max(y0 + 1);
+        let mut sum = [0u64; 3];
+        for sy in y0..y1 {
+            for sx in x0..x1 {
+                let pixel = image.get_pixel(sx, sy).0;
+                for (total, channel) in sum.iter_mut().zip(pixel) {
+                    *total += channel as u64;
+                }
+            }
+        }
+        let count = ((x1 - x0) * (y1 - y0)) as u64;
+        image::Rgb(sum.map(|total| (total / count) as u8))
+    })
+}
@@ -1,119 +1,387 @@
 // src/ui/mod.rs
+use std::collections::{HashMap, HashSet};
 use std::io;
-use std::time::Duration;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use crossterm::{
+    cursor::Show,
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
     backend::{Backend, CrosstermBackend},
-    layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap},
     Frame, Terminal,
 };
-use textwrap::fill;
+use textwrap::{fill, wrap};
 use unicode_width::UnicodeWidthStr;
 
-use crate::epub::EpubDocument;
-use crate::navigation::Navigator;
-use crate::metadata::Metadata;
+mod image_art;
+mod prefetch;
+mod theme;
+mod word_count;
+
+use prefetch::Prefetcher;
+use theme::Theme;
+use word_count::WordCounter;
+
+use crate::clipboard::Clipboard;
+use crate::config::AppConfig;
+use crate::epub::{EpubDocument, ReadingDirection, TocSource};
+use crate::errors::EpubError;
+use crate::i18n::{Lang, Msg};
+use crate::navigation::{Navigator, TocEntry};
+use crate::recent::RecentBooks;
+use crate::path_utils;
+use crate::render::{image_label_for_language, CaptionLabels, HeadingStyle, ImageRef, LinkRef, ListStyle, ParagraphStyle, QuoteStyle, RenderOptions, RenderedChapter, StyleRange, TextStyle, PARAGRAPH_INDENT, SCENE_BREAK};
+
+// Comandos conocidos, usados para autocompletar con Tab en el modo comando
+const COMMANDS: &[&str] = &[
+    "advance", "chapters", "count", "cover", "export", "export-html", "export-toc", "first", "goto", "goto-label", "headings", "info", "landmarks", "last", "meta", "next", "open", "open-id",
+    "page", "paragraphs", "prev", "quit", "set", "style", "theme", "toc", "width", "yank", "zen",
+];
+
+// Límites y paso de la velocidad del auto-scroll (palabras por minuto)
+const MIN_WPM: u32 = 50;
+const MAX_WPM: u32 = 1000;
+const WPM_STEP: i32 = 25;
+// Páginas iniciales que se consideran portada o portadilla si el libro no marca dónde
+// empieza el texto: como mucho FRONT_MATTER_MAX_PAGES, cada una con menos de
+// FRONT_MATTER_MAX_WORDS palabras. Un primer capítulo real, aunque sea corto, las supera
+const FRONT_MATTER_MAX_PAGES: usize = 3;
+const FRONT_MATTER_MAX_WORDS: usize = 20;
 
 // Modos de la aplicación
 pub enum AppMode {
     Normal,
     Command,
+    // Búsqueda dentro del capítulo con "/", saltando a la coincidencia mientras se escribe.
+    // origin_scroll es el scroll al empezar, para restaurarlo con Esc
+    Search { query: String, origin_scroll: u16 },
+}
+
+// El capítulo ajustado a un ancho, tal como se ve y en minúsculas, para buscar en él
+#[derive(Default)]
+pub struct SearchLines {
+    pub width: usize,
+    pub lines: Vec<String>,
+    pub lowered: Vec<String>,
 }
 
 // Estado de la aplicación
-pub struct App<'a> {
-    pub epub_doc: &'a mut EpubDocument,
+pub struct App {
+    pub epub_doc: EpubDocument,
+    pub book_path: PathBuf,      // Ruta del EPUB abierto, para recordar la posición
+    pub recent: RecentBooks,
+    pub clipboard: Clipboard,
     pub navigator: Navigator,
     pub current_content: String,
+    pub links: Vec<LinkRef>,     // Enlaces del capítulo actual
+    pub anchors: HashMap<String, usize>, // id -> línea del capítulo, para saltar a #fragmentos
+    pub styles: Vec<StyleRange>, // Negrita, cursiva y encabezados del capítulo actual
+    pub chapter_title: Option<String>, // <title> del archivo actual, por si la TOC no lo nombra
+    pub footnotes: HashMap<String, String>, // Notas al pie del capítulo actual por id
+    pub footnote_popup: Option<String>, // Nota al pie abierta en la ventana emergente
+    pub stylesheets: HashMap<String, String>, // Hojas de estilo ya leídas, por ruta en el ZIP
+    pub prefetcher: Prefetcher,  // Lee en segundo plano los capítulos anterior y siguiente
+    pub images: Vec<ImageRef>,   // Imágenes del capítulo actual
+    pub show_images: bool,       // Se permite mostrar imágenes (config images = true)
+    pub image_popup: Option<Vec<Line<'static>>>, // Imagen abierta como arte de bloques
+    pub position_history: Vec<(usize, u16)>, // (capítulo, scroll) antes de seguir un enlace
     pub command_input: String,
+    pub command_history: Vec<String>,    // Comandos ejecutados en esta sesión
+    pub history_index: Option<usize>,    // Posición al recorrer el historial con Up/Down
+    pub completion: Option<(String, usize)>, // Prefijo escrito y candidato actual al pulsar Tab
+    pub aliases: HashMap<String, String>, // Alias de comandos definidos en la configuración
     pub mode: AppMode,
     pub status_message: String,
     pub scroll_offset: u16,      // Scroll para el contenido del capítulo
+    pub search_lines: SearchLines, // Se prepara al abrir la búsqueda y se conserva mientras n/N la recorren
+    pub search_matches: Vec<usize>, // Líneas (ajustadas) que contienen la búsqueda actual
+    pub search_current: usize,   // Coincidencia en la que está el lector (índice de search_matches)
+    pub accepted_search: String, // Búsqueda aceptada con Enter que n/N recorren (vacía = ninguna)
     pub toc_scroll_offset: u16,  // Scroll exclusivo para la tabla de contenidos
+    pub toc_max_depth: Option<usize>, // Profundidad máxima mostrada en la TOC (None = todas)
+    pub toc_selected: usize,     // Entrada seleccionada en la vista de la TOC (índice de la TOC aplanada)
+    pub toc_collapsed: HashSet<usize>, // Entradas de la TOC plegadas, cuyas subentradas no se muestran
+    pub meta_scroll_offset: u16, // Scroll de la vista de metadatos (descripciones largas)
+    pub content_height: u16,     // Altura del área de contenido en el último renderizado
+    pub content_width: u16,      // Ancho de ajuste del texto en el último renderizado
+    pub content_line_count: usize, // Líneas del capítulo tras ajustar al ancho actual
+    pub wrapped_lines: Vec<Line<'static>>, // Capítulo ajustado y justificado al ancho wrapped_width
+    pub wrapped_width: usize,
+    pub content_dirty: bool,     // El capítulo cambió y wrapped_lines debe recalcularse
+    pub screens_read: u64,       // Pantallas de texto recorridas hacia delante en esta sesión
+    pub lines_toward_screen: usize, // Líneas avanzadas que aún no completan una pantalla
     pub should_quit: bool,
+    pub quit_pending: bool,      // No se pudo guardar la posición: el siguiente q sale igualmente
+    pub auto_advance: bool,      // Pasar de capítulo al desplazarse más allá de los bordes
+    pub auto_scroll: bool,       // Modo teleprompter: avanza una línea cada cierto tiempo
+    pub wpm: u32,                // Velocidad del auto-scroll en palabras por minuto
+    pub scroll_step: u16,        // Líneas que avanzan j/k
+    pub auto_scroll_due: Instant, // Momento en que toca avanzar la siguiente línea
+    pub max_line_width: Option<usize>, // Ancho máximo del texto (None = todo el ancho)
+    pub rtl: bool,               // El capítulo actual se escribe de derecha a izquierda
+    pub render_options: RenderOptions,
+    pub show_scrollbar: bool,    // Barra de desplazamiento en el borde derecho del texto y la TOC
+    pub rich_text: bool,         // Mostrar el énfasis con atributos del terminal en vez de marcadores
+    pub theme: Theme,
+    pub lang: Lang,              // Idioma de los textos de la interfaz
     pub show_metadata: bool,
+    pub show_landmarks: bool,
+    pub show_info: bool,         // Diagnóstico del parseo (:info)
+    pub zen_mode: bool,          // Sin barras superior e inferior: todo el terminal para el texto
+    pub show_chapters: bool,     // Lista de todos los items del spine con su número de palabras
+    pub chapters_selected: usize, // Item del spine seleccionado en esa lista
+    pub word_counter: Option<WordCounter>, // Palabras por item del spine; empieza al abrir la lista
     pub show_toc: bool,
+    pub offer_skip: bool,        // El capítulo actual no se pudo leer: Enter salta al siguiente
+    pub skip_backwards: bool,    // Se llegó al capítulo retrocediendo: si está vacío se salta hacia atrás
 }
 
-impl<'a> App<'a> {
-    pub fn new(epub_doc: &'a mut EpubDocument) -> Self {
-        let navigator = epub_doc.create_navigator();
+impl App {
+    pub fn new(epub_doc: EpubDocument, book_path: PathBuf, config: &AppConfig) -> Self {
+        let lang = Lang::detect(&config.language);
+        let navigator = create_navigator(&epub_doc, lang);
+        let mut render_options = RenderOptions {
+            paragraph_style: paragraph_style_by_name(&config.paragraph_style).unwrap_or(ParagraphStyle::Spaced),
+            list_style: list_style_by_name(&config.list_style).unwrap_or(ListStyle::Dash),
+            keep_links: config.show_links,
+            quote_style: QuoteStyle::for_language(epub_doc.metadata.language.as_deref()),
+            caption_labels: CaptionLabels::for_language(epub_doc.metadata.language.as_deref()),
+            image_label: image_label_for_language(epub_doc.metadata.language.as_deref()),
+            ..RenderOptions::default()
+        };
+        let rich_text = apply_text_style(&mut render_options, &config.style);
+        let prefetcher = Prefetcher::start(epub_doc.chapter_reader().ok());
         App {
             epub_doc,
+            book_path,
+            recent: RecentBooks::load(),
+            clipboard: Clipboard::new(),
             navigator,
             current_content: String::new(),
+            links: Vec::new(),
+            anchors: HashMap::new(),
+            styles: Vec::new(),
+            chapter_title: None,
+            footnotes: HashMap::new(),
+            footnote_popup: None,
+            stylesheets: HashMap::new(),
+            prefetcher,
+            images: Vec::new(),
+            show_images: config.images,
+            image_popup: None,
+            position_history: Vec::new(),
             command_input: String::new(),
+            command_history: Vec::new(),
+            history_index: None,
+            completion: None,
+            aliases: config.aliases.clone(),
             mode: AppMode::Normal,
             status_message: String::new(),
             scroll_offset: 0,
+            search_lines: SearchLines::default(),
+            search_matches: Vec::new(),
+            search_current: 0,
+            accepted_search: String::new(),
             toc_scroll_offset: 0,
+            toc_max_depth: None,
+            toc_selected: 0,
+            toc_collapsed: HashSet::new(),
+            meta_scroll_offset: 0,
+            content_height: 0,
+            content_width: 0,
+            content_line_count: 0,
+            wrapped_lines: Vec::new(),
+            wrapped_width: 0,
+            content_dirty: true,
+            screens_read: 0,
+            lines_toward_screen: 0,
             should_quit: false,
+            quit_pending: false,
+            auto_advance: config.auto_advance,
+            auto_scroll: false,
+            wpm: config.wpm.clamp(MIN_WPM, MAX_WPM),
+            scroll_step: config.scroll_step.max(1),
+            auto_scroll_due: Instant::now(),
+            max_line_width: Some(config.width).filter(|&width| width > 0),
+            rtl: false,
+            render_options,
+            show_scrollbar: config.scrollbar,
+            rich_text,
+            theme: Theme::by_name(&config.theme).unwrap_or_default(),
+            lang,
             show_metadata: false,
+            show_landmarks: false,
+            show_info: false,
+            zen_mode: false,
+            show_chapters: false,
+            chapters_selected: 0,
+            word_counter: None,
             show_toc: false,
+            offer_skip: false,
+            skip_backwards: false,
         }
     }
 
     // Carga el contenido del capítulo actual
     pub fn load_current_chapter(&mut self) {
+        self.scroll_offset = 0; // Resetear el scroll al cambiar de capítulo
+        self.offer_skip = false;
+        self.content_dirty = true;
+        self.links.clear();
+        self.anchors.clear();
+        self.styles.clear();
+        self.footnotes.clear();
+        self.footnote_popup = None;
+        self.images.clear();
+        self.image_popup = None;
+        self.chapter_title = None;
+        self.clear_search_results();
+        let backwards = std::mem::take(&mut self.skip_backwards);
         match self.navigator.current_chapter_href() {
             Ok(href) => {
-                match self.epub_doc.read_chapter_content(&href) {
-                    Ok(content) => {
-                        let rendered_text = crate::render::render_xhtml_to_text(&content);
-                        self.current_content = rendered_text;
-                        self.scroll_offset = 0; // Resetear el scroll al cambiar de capítulo
-                        self.status_message = format!(
-                            "Capítulo {} de {}",
-                            self.navigator.current_position().0,
-                            self.navigator.current_position().1
-                        );
+                let rendered = match self.prefetcher.take(&href, &self.render_options) {
+                    Some(rendered) => Ok(rendered),
+                    None => self.epub_doc.read_chapter_content(&href)
+                        .map(|content| self.render_chapter(&href, &content)),
+                };
+                match rendered {
+                    Ok(rendered) => {
+                        self.prefetch_neighbours();
+                        let rendered_text = rendered.text;
+                        // Muchos libros repiten su propio título en cada archivo: no aporta nada
+                        self.chapter_title = rendered.title
+                            .filter(|title| Some(title) != self.epub_doc.metadata.title.as_ref());
+                        // La TOC generada a partir del spine toma el título del capítulo al leerlo
+                        let toc_index = self.navigator.toc_index_for_spine(self.navigator.current_position().0 - 1);
+                        if let (TocSource::Spine, Some(toc_index), Some(title)) = (self.epub_doc.toc_source, toc_index, &self.chapter_title) {
+                            self.navigator.set_toc_label(toc_index, title.clone());
+                        }
+                        self.rtl = rendered.rtl.unwrap_or_else(|| {
+                            self.epub_doc.reading_direction == ReadingDirection::Rtl
+                                || self.epub_doc.metadata.is_rtl_language()
+                        });
+                        self.status_message = self.chapter_status();
+                        if rendered_text.trim().is_empty() {
+                            // Capítulos vacíos o solo con imágenes: saltar al siguiente con texto
+                            // en el sentido en que se avanzaba
+                            let (epub_doc, options) = (&mut self.epub_doc, &self.render_options);
+                            let target = self.navigator.find_non_empty(backwards, |candidate| {
+                                epub_doc.read_chapter_content(candidate).is_ok_and(|content| {
+                                    !crate::render::render_xhtml_to_text_with(&content, options).trim().is_empty()
+                                })
+                            });
+                            if let Some(target) = target {
+                                let empty = EpubError::EmptyChapter(href);
+                                self.navigator.goto(target + 1);
+                                self.load_current_chapter();
+                                self.status_message = self.lang.format(Msg::ChapterSkipped, &[&self.status_message, &empty]);
+                                return;
+                            }
+                            // Evitar una pantalla en blanco sin explicación
+                            self.current_content = self.lang.text(Msg::NoReadableContent).to_string();
+                            self.status_message = self.lang.format(Msg::NoTextIn, &[&self.status_message, &href]);
+                        } else {
+                            self.current_content = rendered_text;
+                            self.links = rendered.links;
+                            self.anchors = rendered.anchors;
+                            self.footnotes = rendered.footnotes;
+                            self.images = rendered.images;
+                            // Sin texto enriquecido se conservan solo las marcas de verso
+                            self.styles = match self.rich_text {
+                                true => rendered.styles,
+                                false => rendered.styles.into_iter().filter(|range| range.style == TextStyle::Verse).collect(),
+                            };
+                        }
                     }
                     Err(e) => {
-                        self.current_content = format!("Error al leer el capítulo: {}", e);
-                        self.status_message = "Error al cargar el capítulo".to_string();
+                        self.current_content = self.lang.format(Msg::ChapterReadError, &[&e]);
+                        self.status_message = self.lang.format(Msg::ChapterLoadError, &[&href]);
+                        self.offer_skip = true;
                     }
                 }
             }
             Err(e) => {
-                self.current_content = format!("Error al obtener la ruta del capítulo: {}", e);
-                self.status_message = "Error al obtener la ruta del capítulo".to_string();
+                self.current_content = format!("{}: {}", self.lang.text(Msg::ChapterPathError), e);
+                self.status_message = self.lang.text(Msg::ChapterPathError).to_string();
             }
         }
     }
 
+    // Mensaje de estado con la posición del capítulo y su título: el de su entrada en la
+    // TOC, el <title> del archivo o, si no, la sección de la TOC en la que cae
+    fn chapter_status(&self) -> String {
+        let (current, total) = self.navigator.current_position();
+        let label = self.navigator.toc_label_for_spine(current - 1)
+            .or(self.chapter_title.as_deref())
+            .or_else(|| self.navigator.current_chapter_label());
+        let (chapter, of) = (self.lang.text(Msg::Chapter), self.lang.text(Msg::Of));
+        match label {
+            Some(label) => format!("{} {} {} {} — {}", chapter, current, of, total, label),
+            None => format!("{} {} {} {}", chapter, current, of, total),
+        }
+    }
+
+    // Líneas (tal como se ven, ajustadas al ancho actual) y palabras del capítulo, y la
+    // línea resaltada en la que está el lector
+    fn chapter_counts(&self) -> String {
+        let words = self.current_content.split_whitespace().count();
+        let lines = self.content_line_count;
+        let highlighted = (self.scroll_offset as usize + self.content_height as usize / 2 + 1).min(lines);
+        let chapter = self.navigator.current_position().0;
+        self.lang.format(Msg::ChapterCounts, &[&chapter, &lines, &words, &highlighted, &lines])
+    }
+
+    // Vuelve a renderizar el capítulo actual conservando la posición de scroll
+    pub fn reload_current_chapter(&mut self) {
+        let scroll_offset = self.scroll_offset;
+        self.load_current_chapter();
+        self.scroll_offset = scroll_offset;
+    }
+
     // Navega al siguiente capítulo
     pub fn next_chapter(&mut self) {
         if self.navigator.next() {
             self.load_current_chapter();
-            self.status_message = format!(
-                "Capítulo {} de {}",
-                self.navigator.current_position().0,
-                self.navigator.current_position().1
-            );
         } else {
-            self.status_message = "Ya estás en el último capítulo".to_string();
+            self.status_message = self.lang.text(Msg::LastChapter).to_string();
         }
     }
 
     // Navega al capítulo anterior
     pub fn prev_chapter(&mut self) {
         if self.navigator.prev() {
+            self.skip_backwards = true;
             self.load_current_chapter();
-            self.status_message = format!(
-                "Capítulo {} de {}",
-                self.navigator.current_position().0,
-                self.navigator.current_position().1
-            );
         } else {
-            self.status_message = "Ya estás en el primer capítulo".to_string();
+            self.status_message = self.lang.text(Msg::FirstChapter).to_string();
+        }
+    }
+
+    // Navega al primer capítulo del spine
+    pub fn first_chapter(&mut self) {
+        if self.navigator.current_position().0 == 1 {
+            self.status_message = self.lang.text(Msg::FirstChapter).to_string();
+        } else {
+            self.goto_chapter(1);
+        }
+    }
+
+    // Navega al último capítulo del spine
+    pub fn last_chapter(&mut self) {
+        let (current, total) = self.navigator.current_position();
+        if current == total {
+            self.status_message = self.lang.text(Msg::LastChapter).to_string();
+        } else {
+            self.goto_chapter(total);
         }
     }
 
@@ -121,24 +389,282 @@ impl<'a> App<'a> {
     pub fn goto_chapter(&mut self, index: usize) {
         if self.navigator.goto(index) {
             self.load_current_chapter();
-            self.status_message = format!(
-                "Capítulo {} de {}",
-                self.navigator.current_position().0,
-                self.navigator.current_position().1
-            );
         } else {
-            self.status_message = format!("Capítulo {} no válido", index);
+            self.status_message = self.lang.format(Msg::InvalidChapter, &[&index]);
+        }
+    }
+
+    // Va a un punto del libro entero (0-100 %), repartido según el tamaño sin comprimir de
+    // cada capítulo (lo dice el ZIP sin descomprimir nada); si no se conoce ninguno, cada
+    // capítulo pesa lo mismo
+    fn goto_percent(&mut self, percent: f64) {
+        let total_chapters = self.navigator.total_chapters();
+        let mut weights: Vec<u64> = (0..total_chapters)
+            .map(|spine_index| {
+                self.navigator.spine_href(spine_index).ok()
+                    .and_then(|href| self.epub_doc.entry_size(&href))
+                    .unwrap_or(0)
+            })
+            .collect();
+        if weights.iter().all(|&size| size == 0) {
+            weights = vec![1; total_chapters];
+        }
+        let total: u64 = weights.iter().sum();
+        let target = total as f64 * percent / 100.0;
+
+        // Capítulo en el que cae el objetivo y fracción recorrida dentro de él
+        let mut before = 0.0;
+        let mut chapter = total_chapters.saturating_sub(1);
+        let mut fraction = 1.0;
+        for (spine_index, &size) in weights.iter().enumerate() {
+            let size = size as f64;
+            if size > 0.0 && target < before + size {
+                chapter = spine_index;
+                fraction = (target - before) / size;
+                break;
+            }
+            before += size;
+        }
+
+        if !self.navigator.goto(chapter + 1) {
+            return;
+        }
+        self.load_current_chapter();
+        let line_count = self.current_content.lines().count();
+        let rows = self.wrapped_line_index(line_count).saturating_sub(self.content_height as usize);
+        self.scroll_offset = ((rows as f64 * fraction) as usize).min(u16::MAX as usize) as u16;
+        self.status_message = self.lang.format(Msg::BookPercent, &[&self.chapter_status(), &percent]);
+    }
+
+    // Pide al hilo de precarga los capítulos a los que se suele ir desde el actual
+    fn prefetch_neighbours(&self) {
+        let (current, _) = self.navigator.current_position();
+        let hrefs = [current.checked_sub(2), Some(current)].into_iter()
+            .flatten()
+            .filter_map(|index| self.navigator.spine_href(index).ok())
+            .collect();
+        self.prefetcher.request(hrefs, self.render_options.clone());
+    }
+
+    // Renderiza en este hilo un capítulo que no estaba precargado, con sus hojas de estilo
+    fn render_chapter(&mut self, chapter_href: &str, content: &str) -> RenderedChapter {
+        let epub_doc = &mut self.epub_doc;
+        let class_styles = crate::render::chapter_class_styles(chapter_href, content, &mut self.stylesheets, |path| {
+            epub_doc.read_entry(path).ok()
+        });
+        crate::render::render_chapter_with_css(content, &self.render_options, &class_styles)
+    }
+
+    // Muestra la primera imagen de la línea resaltada
+    fn open_highlighted_image(&mut self) {
+        let highlighted = self.scroll_offset as usize + self.content_height as usize / 2;
+        let source_line = self.source_line_at(highlighted);
+        let Some(src) = self.images.iter().find(|image| image.line == source_line).map(|image| image.src.clone()) else {
+            self.status_message = self.lang.text(Msg::NoImageOnLine).to_string();
+            return;
+        };
+        match self.navigator.current_chapter_href() {
+            Ok(chapter) => self.show_image(&path_utils::resolve(&chapter, &src)),
+            Err(e) => self.status_message = e.to_string(),
+        }
+    }
+
+    // Abre una imagen del EPUB (ruta completa en el ZIP) en una ventana emergente. Si las
+    // imágenes están desactivadas o no se pueden decodificar se queda el texto alternativo
+    fn show_image(&mut self, path: &str) {
+        if !self.show_images {
+            self.status_message = self.lang.text(Msg::ImagesDisabled).to_string();
+            return;
+        }
+        let bytes = match self.epub_doc.read_entry(path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.status_message = self.lang.format(Msg::ImageReadError, &[&e]);
+                return;
+            }
+        };
+        // Se deja sitio para el borde de la ventana
+        let width = self.content_width.saturating_sub(2);
+        let height = self.content_height.saturating_sub(2);
+        match image_art::render_half_blocks(&bytes, width, height) {
+            Some(lines) => {
+                self.image_popup = Some(lines);
+                self.status_message = self.lang.format(Msg::CloseWithEsc, &[&path]);
+            }
+            None => self.status_message = self.lang.format(Msg::UnsupportedImage, &[&path]),
+        }
+    }
+
+    // Oculta o muestra las barras de estado
+    fn toggle_zen_mode(&mut self) {
+        self.zen_mode = !self.zen_mode;
+        self.status_message = if self.zen_mode {
+            self.lang.text(Msg::ZenMode).to_string()
+        } else {
+            self.chapter_status()
+        };
+    }
+
+    // Líneas que avanzan Ctrl-d/Ctrl-u: media pantalla de texto
+    fn half_page(&self) -> u16 {
+        (self.content_height / 2).max(1)
+    }
+
+    // Desplazamiento máximo que aún deja la última línea del capítulo en pantalla
+    pub fn max_scroll(&self) -> u16 {
+        let max = self.content_line_count.saturating_sub(self.content_height as usize);
+        max.min(u16::MAX as usize) as u16
+    }
+
+    // Progreso dentro del capítulo actual para la barra inferior: "[cap 34%]",
+    // o "[cap FIN]" cuando se ve el final del capítulo
+    pub fn chapter_progress(&self) -> String {
+        let max_scroll = self.max_scroll();
+        if self.scroll_offset >= max_scroll {
+            self.lang.text(Msg::ChapterEnd).to_string()
+        } else {
+            let percent = self.scroll_offset as usize * 100 / max_scroll as usize;
+            self.lang.format(Msg::ChapterPercent, &[&percent])
+        }
+    }
+
+    // Activa o pausa el auto-scroll
+    fn toggle_auto_scroll(&mut self) {
+        self.auto_scroll = !self.auto_scroll;
+        if self.auto_scroll {
+            self.schedule_auto_scroll();
+            self.status_message = self.lang.format(Msg::AutoScrollOn, &[&self.wpm]);
+        } else {
+            self.status_message = self.lang.text(Msg::AutoScrollPaused).to_string();
+        }
+    }
+
+    // Cambia la velocidad del auto-scroll en palabras por minuto
+    fn change_wpm(&mut self, delta: i32) {
+        self.wpm = self.wpm.saturating_add_signed(delta).clamp(MIN_WPM, MAX_WPM);
+        self.status_message = self.lang.format(Msg::AutoScrollSpeed, &[&self.wpm]);
+        if self.auto_scroll {
+            self.schedule_auto_scroll();
+        }
+    }
+
+    // Programa el siguiente avance: la línea resaltada se mantiene el tiempo que se
+    // tarda en leer sus palabras a la velocidad elegida
+    fn schedule_auto_scroll(&mut self) {
+        let words = self.current_line()
+            .map(|line| line.split_whitespace().count())
+            .unwrap_or(0)
+            .max(1) as u64;
+        let delay = Duration::from_millis(words * 60_000 / self.wpm as u64);
+        self.auto_scroll_due = Instant::now() + delay;
+    }
+
+    // Llamado en cada vuelta del bucle principal: avanza una línea si ya toca. Al final
+    // del capítulo pasa al siguiente si el avance automático está activo, o se detiene
+    fn auto_scroll_tick(&mut self) {
+        let reading = matches!(self.mode, AppMode::Normal) && !self.showing_overlay();
+        if !self.auto_scroll || !reading || Instant::now() < self.auto_scroll_due {
+            return;
+        }
+        if self.scroll_offset >= self.max_scroll() {
+            if self.auto_advance && self.navigator.next() {
+                self.load_current_chapter();
+                self.count_screen();
+            } else {
+                self.auto_scroll = false;
+                self.status_message = self.lang.text(Msg::AutoScrollStopped).to_string();
+                return;
+            }
+        } else {
+            self.scroll_offset += 1;
+            self.count_lines_read(1);
+        }
+        self.schedule_auto_scroll();
+    }
+
+    // Suma líneas avanzadas al contador de pantallas leídas
+    fn count_lines_read(&mut self, lines: usize) {
+        let screen = (self.content_height as usize).max(1);
+        self.lines_toward_screen += lines;
+        self.screens_read += (self.lines_toward_screen / screen) as u64;
+        self.lines_toward_screen %= screen;
+    }
+
+    // Pasar de capítulo al llegar al final cuenta como una pantalla leída
+    fn count_screen(&mut self) {
+        self.screens_read += 1;
+        self.lines_toward_screen = 0;
+    }
+
+    // Desplaza el contenido hacia abajo. Si ya estamos al final del capítulo
+    // y el avance automático está activo, pasa al inicio del siguiente.
+    pub fn scroll_down(&mut self, lines: u16) {
+        let max_scroll = self.max_scroll();
+        if self.scroll_offset >= max_scroll {
+            if self.auto_advance && self.navigator.next() {
+                self.load_current_chapter();
+                self.count_screen();
+            } else if self.auto_advance {
+                self.status_message = self.lang.text(Msg::LastChapter).to_string();
+            } else {
+                self.scroll_offset = max_scroll;
+            }
+            return;
+        }
+        let previous = self.scroll_offset;
+        self.scroll_offset = self.scroll_offset.saturating_add(lines).min(max_scroll);
+        self.count_lines_read((self.scroll_offset - previous) as usize);
+    }
+
+    // Desplaza el contenido hacia arriba. Si ya estamos al inicio del capítulo
+    // y el avance automático está activo, pasa al final del anterior.
+    pub fn scroll_up(&mut self, lines: u16) {
+        if self.scroll_offset == 0 {
+            if self.auto_advance && self.navigator.prev() {
+                self.skip_backwards = true;
+                self.load_current_chapter();
+                // render_content lo ajusta a la última pantalla del capítulo
+                self.scroll_offset = u16::MAX;
+            }
+            return;
         }
+        self.scroll_offset = self.scroll_offset.min(self.max_scroll()).saturating_sub(lines);
+    }
+
+    // Avanza una pantalla completa (dejando una línea de solapamiento)
+    pub fn page_down(&mut self) {
+        self.scroll_down(self.content_height.saturating_sub(1).max(1));
+    }
+
+    // Retrocede una pantalla completa (dejando una línea de solapamiento)
+    pub fn page_up(&mut self) {
+        self.scroll_up(self.content_height.saturating_sub(1).max(1));
     }
 
     // Procesa la entrada de comandos
     pub fn process_command(&mut self) {
+        // Guardar en el historial (sin repetir el último comando), tal como se escribió
+        let entered = self.command_input.trim();
+        if !entered.is_empty() && self.command_history.last().map(String::as_str) != Some(entered) {
+            self.command_history.push(entered.to_string());
+        }
+        self.history_index = None;
+        self.completion = None;
+
+        // Los alias se expanden una sola vez: el resultado no se vuelve a buscar entre
+        // los alias, así que un alias que se nombra a sí mismo no crea un bucle
+        if let Some(expanded) = self.expand_alias(self.command_input.trim()) {
+            self.command_input = expanded;
+        }
         let cmd = self.command_input.trim().to_lowercase();
         let parts: Vec<&str> = cmd.split_whitespace().collect();
 
         match parts.as_slice() {
             ["q"] | ["quit"] => {
-                self.should_quit = true;
+                self.quit(false);
+            }
+            ["q!"] | ["quit!"] => {
+                self.quit(true);
             }
             ["n"] | ["next"] => {
                 self.next_chapter();
@@ -146,50 +672,1070 @@ impl<'a> App<'a> {
             ["p"] | ["prev"] => {
                 self.prev_chapter();
             }
+            ["first"] => {
+                self.first_chapter();
+            }
+            ["last"] => {
+                self.last_chapter();
+            }
+            ["g", index_str] | ["goto", index_str] if index_str.ends_with('%') => {
+                match index_str.trim_end_matches('%').parse::<f64>() {
+                    Ok(percent) if (0.0..=100.0).contains(&percent) => self.goto_percent(percent),
+                    _ => self.status_message = self.lang.format(Msg::InvalidPercent, &[index_str]),
+                }
+            }
             ["g", index_str] | ["goto", index_str] => {
                 if let Ok(index) = index_str.parse::<usize>() {
                     self.goto_chapter(index);
                 } else {
-                    self.status_message = format!("Número de capítulo inválido: {}", index_str);
+                    self.status_message = self.lang.format(Msg::InvalidChapterNumber, &[index_str]);
                 }
             }
+            ["goto-label", label @ ..] if !label.is_empty() => {
+                self.goto_label(&label.join(" "));
+            }
+            ["goto-label"] => {
+                self.status_message = self.lang.text(Msg::GotoLabelUsage).to_string();
+            }
+            ["open-id"] => {
+                self.status_message = match self.navigator.current_chapter_id() {
+                    Some(id) => self.lang.format(Msg::CurrentChapterId, &[&id]),
+                    None => self.lang.text(Msg::NoChapterId).to_string(),
+                };
+            }
+            ["open-id", _] => {
+                // Los ids del manifiesto distinguen mayúsculas
+                let id = self.command_argument();
+                self.open_manifest_id(&id);
+            }
+            ["open-id", ..] => {
+                self.status_message = self.lang.text(Msg::OpenIdUsage).to_string();
+            }
             ["t"] | ["toc"] => {
-                self.show_toc = true;
-                self.show_metadata = false;
-                self.toc_scroll_offset = 0; // Reiniciar scroll de TOC al entrar
+                self.open_toc(None);
+            }
+            ["t", depth_str] | ["toc", depth_str] => {
+                match depth_str.parse::<usize>() {
+                    Ok(depth) if depth > 0 => self.open_toc(Some(depth)),
+                    _ => self.status_message = self.lang.format(Msg::InvalidTocDepth, &[depth_str]),
+                }
             }
             ["m"] | ["meta"] => {
                 self.show_metadata = true;
+                self.meta_scroll_offset = 0;
+                self.show_toc = false;
+                self.show_landmarks = false;
+                self.show_info = false;
+                self.show_chapters = false;
+            }
+            ["landmarks"] => {
+                if self.epub_doc.landmarks.is_empty() {
+                    self.status_message = self.lang.text(Msg::NoLandmarks).to_string();
+                } else {
+                    self.show_landmarks = true;
+                    self.show_info = false;
+                    self.show_toc = false;
+                    self.show_metadata = false;
+                    self.show_chapters = false;
+                }
+            }
+            ["cover"] => match self.epub_doc.cover_href() {
+                Some(href) => self.show_image(&href),
+                None => self.status_message = self.lang.text(Msg::NoCover).to_string(),
+            },
+            ["zen"] => {
+                self.toggle_zen_mode();
+            }
+            ["info"] => {
+                self.show_info = true;
                 self.show_toc = false;
+                self.show_metadata = false;
+                self.show_landmarks = false;
+                self.show_chapters = false;
+            }
+            ["chapters"] => {
+                self.show_chapters = true;
+                self.show_toc = false;
+                self.show_metadata = false;
+                self.show_landmarks = false;
+                self.show_info = false;
+                self.chapters_selected = self.navigator.current_position().0 - 1;
+                if self.word_counter.is_none() {
+                    let hrefs = (0..self.navigator.total_chapters())
+                        .filter_map(|spine_index| self.navigator.spine_href(spine_index).ok())
+                        .collect();
+                    self.word_counter = Some(WordCounter::start(self.epub_doc.chapter_reader().ok(), hrefs));
+                }
+            }
+            ["style", style @ ("rich" | "plain" | "markdown")] => {
+                self.rich_text = apply_text_style(&mut self.render_options, style);
+                self.reload_current_chapter();
+                self.status_message = self.lang.format(Msg::TextStyleSet, &[style]);
+            }
+            ["paragraphs", name] => {
+                match paragraph_style_by_name(name) {
+                    Some(paragraph_style) => {
+                        self.render_options.paragraph_style = paragraph_style;
+                        self.reload_current_chapter();
+                        self.status_message = self.lang.format(Msg::ParagraphsSet, &[name]);
+                    }
+                    None => self.status_message = self.lang.format(Msg::UnknownParagraphStyle, &[name]),
+                }
+            }
+            ["headings", mode @ ("numbered" | "plain")] => {
+                self.render_options.numbered_headings = *mode == "numbered";
+                self.reload_current_chapter();
+                self.status_message = self.lang.format(Msg::HeadingsSet, &[mode]);
+            }
+            ["headings", mode] => {
+                self.status_message = self.lang.format(Msg::UnknownHeadings, &[mode]);
+            }
+            ["style", style] => {
+                self.status_message = self.lang.format(Msg::UnknownStyle, &[style]);
+            }
+            ["open", ..] => {
+                let path = self.command_argument();
+                if path.is_empty() {
+                    self.status_message = self.lang.text(Msg::OpenUsage).to_string();
+                } else {
+                    self.open_book(&path);
+                }
+            }
+            ["export", ..] => {
+                let path = self.command_argument();
+                self.export_chapter(&path, false);
+            }
+            ["export-html", ..] => {
+                let path = self.command_argument();
+                self.export_chapter(&path, true);
+            }
+            ["export-toc", ..] => {
+                let argument = self.command_argument();
+                let mut json = false;
+                let mut path = Vec::new();
+                for word in argument.split_whitespace() {
+                    match word {
+                        "--json" => json = true,
+                        _ => path.push(word),
+                    }
+                }
+                if path.is_empty() {
+                    self.status_message = self.lang.text(Msg::ExportTocUsage).to_string();
+                } else {
+                    self.export_toc(&path.join(" "), json);
+                }
+            }
+            ["count"] => {
+                self.status_message = self.chapter_counts();
+            }
+            ["yank"] => {
+                let chapter_text = self.current_content.clone();
+                self.copy_to_clipboard(&chapter_text, Msg::ChapterCopied);
+            }
+            ["page", page] => {
+                self.goto_page(page);
+            }
+            ["theme"] => {
+                self.status_message = self.lang.format(Msg::CurrentTheme, &[&self.theme.name]);
+            }
+            ["theme", name] => {
+                match Theme::by_name(name) {
+                    Some(theme) => {
+                        self.theme = theme;
+                        self.content_dirty = true; // Los encabezados usan colores del tema
+                        self.status_message = self.lang.format(Msg::ThemeSet, &[name]);
+                    }
+                    None => self.status_message = self.lang.format(Msg::UnknownTheme, &[name]),
+                }
+            }
+            ["width", width_str] => {
+                match width_str.parse::<usize>() {
+                    Ok(0) => {
+                        self.max_line_width = None;
+                        self.status_message = self.lang.text(Msg::FullWidth).to_string();
+                    }
+                    Ok(width) if width <= u16::MAX as usize => {
+                        self.max_line_width = Some(width);
+                        self.status_message = self.lang.format(Msg::WidthSet, &[&width]);
+                    }
+                    _ => self.status_message = self.lang.format(Msg::InvalidWidth, &[width_str]),
+                }
+            }
+            ["set", "wpm", wpm_str] => {
+                match wpm_str.parse::<u32>() {
+                    Ok(wpm) if (MIN_WPM..=MAX_WPM).contains(&wpm) => {
+                        self.wpm = wpm;
+                        self.status_message = self.lang.format(Msg::WpmSet, &[&wpm]);
+                    }
+                    _ => self.status_message = self.lang.format(Msg::InvalidWpm, &[wpm_str, &MIN_WPM, &MAX_WPM]),
+                }
+            }
+            ["set", "scroll", step_str] => {
+                match step_str.parse::<u16>() {
+                    Ok(step) if step > 0 => {
+                        self.scroll_step = step;
+                        self.status_message = self.lang.format(Msg::ScrollStepSet, &[&step]);
+                    }
+                    _ => self.status_message = self.lang.format(Msg::InvalidScrollStep, &[step_str]),
+                }
+            }
+            ["a"] | ["advance"] => {
+                self.auto_advance = !self.auto_advance;
+                let message = if self.auto_advance { Msg::AutoAdvanceOn } else { Msg::AutoAdvanceOff };
+                self.status_message = self.lang.text(message).to_string();
             }
             [] => {
                 // Comando vacío, no hacer nada
             }
-            _ => {
-                self.status_message = format!("Comando desconocido: {}", cmd);
+            _ => {
+                self.status_message = self.lang.format(Msg::UnknownCommand, &[&cmd]);
+            }
+        }
+
+        self.command_input.clear();
+        self.mode = AppMode::Normal;
+    }
+
+    // Sustituye el libro actual por otro. Si no se puede abrir, se conserva el actual
+    fn open_book(&mut self, path: &str) {
+        let epub_doc = match EpubDocument::open(Path::new(path)) {
+            Ok(doc) => doc,
+            Err(e) => {
+                self.status_message = self.lang.format(Msg::OpenFailed, &[&path, &e]);
+                return;
+            }
+        };
+        let navigator = create_navigator(&epub_doc, self.lang);
+        if navigator.total_chapters() == 0 {
+            self.status_message = self.lang.format(Msg::NoChapters, &[&path]);
+            return;
+        }
+
+        // Guardar dónde se quedó el libro anterior antes de cambiarlo
+        let _ = self.remember_position();
+
+        self.render_options.quote_style = QuoteStyle::for_language(epub_doc.metadata.language.as_deref());
+        self.render_options.caption_labels = CaptionLabels::for_language(epub_doc.metadata.language.as_deref());
+        self.render_options.image_label = image_label_for_language(epub_doc.metadata.language.as_deref());
+        self.prefetcher = Prefetcher::start(epub_doc.chapter_reader().ok());
+        self.epub_doc = epub_doc;
+        self.book_path = PathBuf::from(path);
+        self.navigator = navigator;
+        self.show_toc = false;
+        self.show_metadata = false;
+        self.show_landmarks = false;
+        self.show_info = false;
+        self.show_chapters = false;
+        self.word_counter = None;
+        self.stylesheets.clear();
+        self.toc_collapsed.clear();
+        self.toc_scroll_offset = 0;
+        self.toc_max_depth = None;
+        self.position_history.clear();
+        self.resume_or_load();
+    }
+
+    // Carga el capítulo donde se dejó el libro la última vez, o el primero si es nuevo
+    pub fn resume_or_load(&mut self) {
+        let saved = self.recent.find(&self.book_path)
+            .map(|book| (book.chapter, book.scroll_offset));
+        match saved {
+            Some((chapter, scroll_offset)) if self.navigator.goto(chapter) => {
+                self.load_current_chapter();
+                self.scroll_offset = scroll_offset;
+            }
+            _ => self.load_reading_start(),
+        }
+        // Avisar de los problemas del parseo y de los capítulos del spine que se omitieron por
+        // estar rotos; la lista completa está en :info
+        let warnings = self.warnings();
+        if let Some(first) = warnings.first() {
+            let others = warnings.len() - 1;
+            self.status_message = if others == 0 {
+                self.lang.format(Msg::Warning, &[first])
+            } else {
+                self.lang.format(Msg::WarningAndMore, &[first, &others])
+            };
+        }
+    }
+
+    // Advertencias del parseo del libro seguidas de las del navegador
+    fn warnings(&self) -> Vec<String> {
+        self.epub_doc.warnings.iter().chain(self.navigator.warnings()).cloned().collect()
+    }
+
+    // Sale guardando antes la posición de lectura. Si no se puede guardar (disco lleno,
+    // directorio de solo lectura...) se avisa y hace falta repetir q (o usar :q!) para salir
+    pub fn quit(&mut self, force: bool) {
+        if self.quit_pending {
+            self.should_quit = true;
+            return;
+        }
+        match self.remember_position() {
+            _ if force => self.should_quit = true,
+            Ok(()) => self.should_quit = true,
+            Err(e) => {
+                self.status_message = self.lang.format(Msg::SavePositionFailed, &[&e]);
+                self.quit_pending = true;
+            }
+        }
+    }
+
+    // Guarda el libro actual y su posición en la lista de recientes
+    pub fn remember_position(&mut self) -> io::Result<()> {
+        let title = self.epub_doc.metadata.title.clone()
+            .or_else(|| self.book_path.file_name().map(|name| name.to_string_lossy().into_owned()))
+            .unwrap_or_default();
+        let (chapter, _) = self.navigator.current_position();
+        self.recent.remember(&self.book_path, &title, chapter, self.scroll_offset);
+        self.recent.save()
+    }
+
+    // Sustituye el primer término del comando si es un alias, conservando los argumentos
+    // ("ir 3" con ir = "goto" da "goto 3")
+    fn expand_alias(&self, command: &str) -> Option<String> {
+        let (name, rest) = command.split_once(char::is_whitespace).unwrap_or((command, ""));
+        let expansion = self.aliases.get(name)?;
+        Some(format!("{} {}", expansion.trim(), rest.trim()).trim().to_string())
+    }
+
+    // Argumento del comando tal como se escribió (sin pasar a minúsculas): las rutas
+    // distinguen mayúsculas y pueden contener espacios
+    fn command_argument(&self) -> String {
+        self.command_input.trim()
+            .split_once(char::is_whitespace)
+            .map(|(_, rest)| rest.trim().to_string())
+            .unwrap_or_default()
+    }
+
+    // Guarda el capítulo actual en un archivo: el texto renderizado o, con html, el XHTML original.
+    // Sin ruta se usa "<título>-ch<N>.txt" (o .xhtml) en el directorio actual
+    fn export_chapter(&mut self, path: &str, html: bool) {
+        let (chapter, _) = self.navigator.current_position();
+        let path = if path.is_empty() {
+            let fallback = self.lang.text(Msg::ExportFileName);
+            let title = self.epub_doc.metadata.title.as_deref().unwrap_or(fallback);
+            let extension = if html { "xhtml" } else { "txt" };
+            PathBuf::from(format!("{}-ch{}.{}", sanitize_file_name(title, fallback), chapter, extension))
+        } else {
+            expand_tilde(path)
+        };
+
+        let content = if html {
+            let chapter_html = self.navigator.current_chapter_href()
+                .and_then(|href| self.epub_doc.read_chapter_content(&href));
+            match chapter_html {
+                Ok(content) => content,
+                Err(e) => {
+                    self.status_message = self.lang.format(Msg::ChapterReadError, &[&e]);
+                    return;
+                }
+            }
+        } else {
+            self.current_content.clone()
+        };
+
+        self.status_message = match std::fs::write(&path, content) {
+            Ok(()) => self.lang.format(Msg::ChapterExported, &[&chapter, &path.display()]),
+            Err(e) => self.lang.format(Msg::WriteFailed, &[&path.display(), &e]),
+        };
+    }
+
+    // Guarda la tabla de contenidos como esquema de texto sangrado o, con json, como JSON
+    fn export_toc(&mut self, path: &str, json: bool) {
+        let toc = self.navigator.get_toc();
+        if toc.is_empty() {
+            self.status_message = self.lang.text(Msg::NoToc).to_string();
+            return;
+        }
+        let content = if json {
+            match serde_json::to_string_pretty(toc) {
+                Ok(content) => content + "\n",
+                Err(e) => {
+                    self.status_message = self.lang.format(Msg::JsonFailed, &[&e]);
+                    return;
+                }
+            }
+        } else {
+            toc.iter().map(|entry| format!("{}\n", entry)).collect()
+        };
+
+        let path = expand_tilde(path);
+        self.status_message = match std::fs::write(&path, content) {
+            Ok(()) => self.lang.format(Msg::TocExported, &[&path.display()]),
+            Err(e) => self.lang.format(Msg::WriteFailed, &[&path.display(), &e]),
+        };
+    }
+
+    // Línea resaltada en el centro de la pantalla, tal como se ajustó al ancho actual
+    fn current_line(&self) -> Option<String> {
+        let index = self.scroll_offset as usize + self.content_height as usize / 2;
+        fill(&self.current_content, self.content_width.max(1) as usize)
+            .lines()
+            .nth(index)
+            .map(|line| line.trim().to_string())
+    }
+
+    // Copia texto al portapapeles e informa del resultado en la barra de estado
+    fn copy_to_clipboard(&mut self, text: &str, success_message: Msg) {
+        self.status_message = match self.clipboard.copy(text) {
+            Ok(()) => self.lang.text(success_message).to_string(),
+            Err(e) => e,
+        };
+    }
+
+    // Va a la página impresa indicada según la page-list del EPUB
+    fn goto_page(&mut self, page: &str) {
+        if self.epub_doc.page_list.is_empty() {
+            self.status_message = self.lang.text(Msg::NoPageList).to_string();
+            return;
+        }
+        let target = self.epub_doc.page_list.iter()
+            .find(|entry| entry.label.eq_ignore_ascii_case(page))
+            .and_then(|entry| {
+                let spine_index = self.navigator.spine_index_for_href(&entry.href)?;
+                Some((spine_index, entry.fragment.clone()))
+            });
+        match target {
+            Some((spine_index, fragment)) => {
+                self.navigator.goto(spine_index + 1);
+                self.load_current_chapter();
+                if let Some(fragment) = fragment {
+                    self.scroll_to_anchor(&fragment);
+                }
+                self.status_message = self.lang.format(Msg::PageFound, &[&page, &self.status_message]);
+            }
+            None => self.status_message = self.lang.format(Msg::PageNotFound, &[&page]),
+        }
+    }
+
+    // Abre el prompt de búsqueda. El texto ajustado se prepara una sola vez aquí, así
+    // cada tecla solo vuelve a buscar la consulta sin renderizar de nuevo el capítulo
+    fn start_search(&mut self) {
+        self.mode = AppMode::Search { query: String::new(), origin_scroll: self.scroll_offset };
+        self.search_matches.clear();
+        self.prepare_search_lines();
+    }
+
+    // Ajusta el capítulo al ancho actual para buscar, salvo que ya esté hecho para ese ancho
+    fn prepare_search_lines(&mut self) {
+        let width = self.content_width.max(1) as usize;
+        if self.search_lines.width == width && !self.search_lines.lines.is_empty() {
+            return;
+        }
+        let lines: Vec<String> = fill(&self.current_content, width).lines().map(str::to_string).collect();
+        let lowered = lines.iter().map(|line| line.to_lowercase()).collect();
+        self.search_lines = SearchLines { width, lines, lowered };
+    }
+
+    // Líneas (índices en search_lines) que contienen la consulta, ya en minúsculas
+    fn matching_lines(&self, lowered_query: &str) -> Vec<usize> {
+        self.search_lines.lowered.iter()
+            .enumerate()
+            .filter(|(_, line)| line.contains(lowered_query))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    // Recalcula las coincidencias de la consulta actual y salta a la primera a partir
+    // de donde empezó la búsqueda (volviendo al principio si no hay ninguna después)
+    fn update_search(&mut self) {
+        let AppMode::Search { query, origin_scroll } = &self.mode else {
+            return;
+        };
+        let query = query.to_lowercase();
+        let origin_scroll = *origin_scroll;
+        if query.is_empty() {
+            self.search_matches.clear();
+            self.scroll_offset = origin_scroll;
+            return;
+        }
+        self.search_matches = self.matching_lines(&query);
+
+        let origin_line = origin_scroll as usize + self.content_height as usize / 2;
+        let target = self.search_matches.iter()
+            .position(|&line| line >= origin_line)
+            .or((!self.search_matches.is_empty()).then_some(0));
+        match target {
+            Some(index) => {
+                self.search_current = index;
+                self.center_search_match();
+            }
+            None => self.scroll_offset = origin_scroll,
+        }
+    }
+
+    // Deja la coincidencia actual en la línea resaltada del centro
+    fn center_search_match(&mut self) {
+        if let Some(&line) = self.search_matches.get(self.search_current) {
+            let target = line.saturating_sub(self.content_height as usize / 2);
+            self.scroll_offset = target.min(u16::MAX as usize) as u16;
+        }
+    }
+
+    // Hay una búsqueda aceptada cuyas coincidencias recorren n/N
+    fn has_search_results(&self) -> bool {
+        !self.accepted_search.is_empty()
+    }
+
+    // Olvida la búsqueda aceptada (n vuelve a pasar de capítulo)
+    fn clear_search_results(&mut self) {
+        self.accepted_search.clear();
+        self.search_lines = SearchLines::default();
+        self.search_matches.clear();
+        self.search_current = 0;
+    }
+
+    // Pasa a la coincidencia siguiente o anterior de la búsqueda aceptada (dando la vuelta
+    // al llegar a un extremo) y muestra cuál es y su línea. Si el ancho cambió desde la
+    // búsqueda, el capítulo se vuelve a ajustar y las coincidencias se recalculan
+    fn cycle_search(&mut self, forward: bool) {
+        if self.search_lines.width != self.content_width.max(1) as usize {
+            self.prepare_search_lines();
+            self.search_matches = self.matching_lines(&self.accepted_search.to_lowercase());
+        }
+        let total = self.search_matches.len();
+        if total == 0 {
+            self.status_message = format!("{} \"{}\"", self.lang.text(Msg::NoMatchesFor), self.accepted_search);
+            return;
+        }
+        self.search_current = match forward {
+            true => (self.search_current + 1) % total,
+            false => (self.search_current.min(total - 1) + total - 1) % total,
+        };
+        self.center_search_match();
+        let context = self.search_lines.lines[self.search_matches[self.search_current]].trim();
+        self.status_message = format!(
+            "{} {} {} {}: {}",
+            self.lang.text(Msg::Match), self.search_current + 1, self.lang.text(Msg::Of), total, context
+        );
+    }
+
+    // Termina la búsqueda: Enter conserva la posición, Esc vuelve al punto de partida
+    fn finish_search(&mut self, confirm: bool) {
+        let AppMode::Search { query, origin_scroll } = std::mem::replace(&mut self.mode, AppMode::Normal) else {
+            return;
+        };
+        if !confirm {
+            self.scroll_offset = origin_scroll;
+            self.clear_search_results();
+            return;
+        }
+        self.status_message = if query.is_empty() {
+            self.chapter_status()
+        } else if self.search_matches.is_empty() {
+            format!("{} \"{}\"", self.lang.text(Msg::NoMatchesFor), query)
+        } else {
+            format!(
+                "{} {} {} {} \"{}\" (n/N)",
+                self.lang.text(Msg::Match), self.search_current + 1, self.lang.text(Msg::Of),
+                self.search_matches.len(), query
+            )
+        };
+        // n/N recorren las coincidencias hasta que se cambie de capítulo o se pulse Esc
+        if self.search_matches.is_empty() {
+            self.clear_search_results();
+        } else {
+            self.accepted_search = query;
+        }
+    }
+
+    // Sigue el enlace de la línea resaltada. Si la línea tiene varios, se usa el
+    // primero cuyo texto se ve en ella
+    fn follow_link(&mut self) {
+        let highlighted = self.scroll_offset as usize + self.content_height as usize / 2;
+        let source_line = self.source_line_at(highlighted);
+        let line_text = self.current_line().unwrap_or_default();
+        let mut candidates = self.links.iter().filter(|link| link.line == source_line);
+        let first = candidates.clone().next();
+        let link = candidates
+            .find(|link| !link.text.is_empty() && line_text.contains(&link.text))
+            .or(first);
+        match link.map(|link| link.href.clone()) {
+            Some(href) => self.open_link(&href),
+            None => self.status_message = self.lang.text(Msg::NoLinkOnLine).to_string(),
+        }
+    }
+
+    // Navega al destino de un enlace interno (otro capítulo y/o #fragmento),
+    // guardando la posición actual para volver con Ctrl-o
+    fn open_link(&mut self, href: &str) {
+        if href.contains("://") || href.starts_with("mailto:") {
+            self.status_message = self.lang.format(Msg::ExternalLink, &[&href]);
+            return;
+        }
+        let (path, fragment) = match href.split_once('#') {
+            Some((path, fragment)) => (path, Some(fragment)),
+            None => (href, None),
+        };
+
+        let (chapter, _) = self.navigator.current_position();
+        let target_index = if path.is_empty() {
+            Some(chapter - 1)
+        } else {
+            self.navigator.current_chapter_href().ok()
+                .map(|current| path_utils::resolve(&current, path))
+                .and_then(|target| self.navigator.spine_index_for_href(&target))
+        };
+        let Some(target_index) = target_index else {
+            self.status_message = self.lang.format(Msg::LinkOutsideBook, &[&href]);
+            return;
+        };
+
+        // Las notas al pie del propio capítulo se muestran en una ventana emergente sin moverse
+        if let Some(note) = fragment.filter(|_| target_index + 1 == chapter).and_then(|id| self.footnotes.get(id)) {
+            self.footnote_popup = Some(note.clone());
+            self.status_message = self.lang.text(Msg::FootnoteOpened).to_string();
+            return;
+        }
+
+        self.position_history.push((chapter, self.scroll_offset));
+        if target_index + 1 != chapter {
+            self.navigator.goto(target_index + 1);
+            self.load_current_chapter();
+        }
+        let found = fragment.is_none_or(|fragment| self.scroll_to_anchor(fragment));
+        self.status_message = if found {
+            self.lang.text(Msg::LinkFollowed).to_string()
+        } else {
+            self.lang.format(Msg::AnchorNotFound, &[&href])
+        };
+    }
+
+    // Vuelve a la posición anterior a seguir el último enlace
+    fn go_back(&mut self) {
+        let Some((chapter, scroll_offset)) = self.position_history.pop() else {
+            self.status_message = self.lang.text(Msg::NoPreviousPositions).to_string();
+            return;
+        };
+        if self.navigator.current_position().0 != chapter {
+            self.navigator.goto(chapter);
+            self.load_current_chapter();
+        }
+        self.scroll_offset = scroll_offset;
+        self.status_message = self.chapter_status();
+    }
+
+    // Salta a la siguiente (o anterior) entrada de la TOC según la posición actual. Las
+    // entradas del capítulo abierto se ordenan por el desplazamiento de su ancla; las de
+    // otros capítulos, por su posición en el spine
+    fn jump_section(&mut self, forward: bool) {
+        let current = (self.navigator.current_position().0 - 1, self.scroll_offset.min(self.max_scroll()) as usize);
+        let toc_len = self.navigator.get_toc().len();
+        let mut best: Option<((usize, usize), usize)> = None;
+        for toc_index in 0..toc_len {
+            let Some(spine_index) = self.navigator.spine_index_for_toc(toc_index) else {
+                continue;
+            };
+            let offset = match &self.navigator.get_toc()[toc_index].fragment {
+                Some(fragment) if spine_index == current.0 => match self.anchors.get(fragment) {
+                    Some(&line) => self.wrapped_line_index(line).saturating_sub(self.content_height as usize / 2),
+                    None => 0,
+                },
+                _ => 0,
+            };
+            let position = (spine_index, offset);
+            // Ante empates gana la primera entrada de la TOC hacia delante y la última hacia atrás
+            let better = match best {
+                None => true,
+                Some((best_position, _)) if forward => position < best_position,
+                Some((best_position, _)) => position >= best_position,
+            };
+            let valid = if forward { position > current } else { position < current };
+            if valid && better {
+                best = Some((position, toc_index));
+            }
+        }
+        let Some((_, toc_index)) = best else {
+            self.status_message = self.lang.text(Msg::NoMoreSections).to_string();
+            return;
+        };
+
+        if self.open_toc_entry(toc_index) {
+            self.status_message = self.lang.format(Msg::Section, &[&self.navigator.get_toc()[toc_index].label]);
+        }
+    }
+
+    // Va a la entrada de la TOC indicada (capítulo y fragmento). Devuelve false si la
+    // entrada no apunta a ningún item del spine
+    fn open_toc_entry(&mut self, toc_index: usize) -> bool {
+        let (chapter, _) = self.navigator.current_position();
+        if !self.navigator.goto_toc(toc_index) {
+            return false;
+        }
+        if self.navigator.current_position().0 != chapter {
+            self.load_current_chapter();
+        }
+        self.scroll_offset = 0;
+        if let Some(fragment) = self.navigator.toc_fragment(toc_index).map(str::to_string) {
+            self.scroll_to_anchor(&fragment);
+        }
+        true
+    }
+
+    // Va a la primera entrada de la TOC cuyo título contiene el texto (sin distinguir
+    // mayúsculas) y que lleva a algún capítulo, avisando si había más coincidencias
+    fn goto_label(&mut self, query: &str) {
+        let matches = self.navigator.find_toc_label(query);
+        let Some(&first) = matches.first() else {
+            self.status_message = self.lang.format(Msg::NoTocEntryContains, &[&query]);
+            return;
+        };
+        self.open_toc_entry(first);
+        let label = &self.navigator.get_toc()[first].label;
+        self.status_message = match matches.len() {
+            1 => self.lang.format(Msg::Section, &[label]),
+            count => self.lang.format(Msg::SectionOfMatches, &[label, &count]),
+        };
+    }
+
+    // Va al item del spine con ese id del manifiesto (tal como aparece en el OPF)
+    fn open_manifest_id(&mut self, id: &str) {
+        match self.navigator.spine_index_for_id(id) {
+            Some(spine_index) => self.goto_chapter(spine_index + 1),
+            None if self.epub_doc.manifest.contains_key(id) => {
+                self.status_message = self.lang.format(Msg::IdNotInSpine, &[&id]);
+            }
+            None => self.status_message = self.lang.format(Msg::IdNotInManifest, &[&id]),
+        }
+    }
+
+    // Desplaza el capítulo para que el elemento con ese id quede en la línea resaltada
+    fn scroll_to_anchor(&mut self, id: &str) -> bool {
+        let Some(&line) = self.anchors.get(id) else {
+            return false;
+        };
+        let wrapped = self.wrapped_line_index(line);
+        let target = wrapped.saturating_sub(self.content_height as usize / 2);
+        self.scroll_offset = target.min(u16::MAX as usize) as u16;
+        true
+    }
+
+    // Primera línea en pantalla (tras ajustar al ancho) de una línea del texto renderizado
+    fn wrapped_line_index(&self, source_line: usize) -> usize {
+        if self.content_width == 0 {
+            return source_line;
+        }
+        self.current_content.lines()
+            .take(source_line)
+            .map(|line| wrap(line, self.content_width as usize).len().max(1))
+            .sum()
+    }
+
+    // Posición en el texto (línea del texto renderizado y byte dentro de ella) con la que
+    // empieza la primera fila visible
+    fn top_text_position(&self) -> (usize, usize) {
+        let row = self.scroll_offset.min(self.max_scroll()) as usize;
+        let source_line = self.source_line_at(row);
+        let paragraph = self.current_content.lines().nth(source_line).unwrap_or("");
+        let wrapped = wrap(paragraph, self.content_width.max(1) as usize);
+        let offsets = wrapped_offsets(paragraph, &wrapped);
+        let index = row.saturating_sub(self.wrapped_line_index(source_line));
+        (source_line, offsets.get(index).copied().unwrap_or(0))
+    }
+
+    // Fila (tras ajustar al ancho actual) en la que queda una posición del texto
+    fn row_for_text_position(&self, (source_line, byte): (usize, usize)) -> usize {
+        let paragraph = self.current_content.lines().nth(source_line).unwrap_or("");
+        let wrapped = wrap(paragraph, self.content_width.max(1) as usize);
+        let offsets = wrapped_offsets(paragraph, &wrapped);
+        let index = offsets.partition_point(|&offset| offset <= byte).saturating_sub(1);
+        self.wrapped_line_index(source_line) + index
+    }
+
+    // Línea del texto renderizado a la que pertenece una línea en pantalla
+    fn source_line_at(&self, wrapped_index: usize) -> usize {
+        if self.content_width == 0 {
+            return wrapped_index;
+        }
+        let mut first_wrapped = 0;
+        for (source_line, line) in self.current_content.lines().enumerate() {
+            first_wrapped += wrap(line, self.content_width as usize).len().max(1);
+            if wrapped_index < first_wrapped {
+                return source_line;
+            }
+        }
+        self.current_content.lines().count()
+    }
+
+    // Indica si hay una vista superpuesta al texto (TOC, metadatos, landmarks, capítulos)
+    fn showing_overlay(&self) -> bool {
+        self.show_toc || self.show_metadata || self.show_landmarks || self.show_chapters || self.show_info
+    }
+
+    // Número de palabras de un item del spine, leyéndolo ahora; None si no se puede leer
+    fn chapter_word_count(&mut self, spine_index: usize) -> Option<usize> {
+        let href = self.navigator.spine_href(spine_index).ok()?;
+        let content = self.epub_doc.read_chapter_content(&href).ok()?;
+        Some(word_count::count_words(&content))
+    }
+
+    // Para un libro nuevo, va al comienzo del texto (landmark "bodymatter" o <guide type="text">)
+    // saltándose la portada y demás preliminares. Sin esa landmark se saltan las primeras
+    // páginas casi sin texto (ver FRONT_MATTER_MAX_WORDS)
+    fn load_reading_start(&mut self) {
+        let start = self.epub_doc.landmarks.iter()
+            .find(|landmark| landmark.kind.split_whitespace().any(|kind| kind == "bodymatter"))
+            .and_then(|landmark| {
+                let spine_index = self.navigator.spine_index_for_href(&landmark.href)?;
+                Some((spine_index, landmark.fragment.clone()))
+            });
+        let skipped = match start {
+            Some((spine_index, _)) => {
+                self.navigator.goto(spine_index + 1);
+                0
+            }
+            None => {
+                // Siempre queda al menos un capítulo por delante
+                let candidates = FRONT_MATTER_MAX_PAGES.min(self.navigator.total_chapters().saturating_sub(1));
+                let skipped = (0..candidates)
+                    .take_while(|&index| self.chapter_word_count(index).is_some_and(|count| count < FRONT_MATTER_MAX_WORDS))
+                    .count();
+                self.navigator.goto(skipped + 1);
+                skipped
+            }
+        };
+        self.load_current_chapter();
+        if let Some((_, Some(fragment))) = start {
+            self.scroll_to_anchor(&fragment);
+        }
+        let skipped = match skipped {
+            0 => return,
+            1 => self.lang.text(Msg::SkippedOnePage).to_string(),
+            pages => self.lang.format(Msg::SkippedPages, &[&pages]),
+        };
+        self.status_message = self.lang.format(Msg::BackToStart, &[&self.status_message, &skipped]);
+    }
+
+    // Muestra la tabla de contenidos, limitada opcionalmente a una profundidad máxima
+    fn open_toc(&mut self, max_depth: Option<usize>) {
+        self.show_toc = true;
+        self.show_metadata = false;
+        self.show_landmarks = false;
+        self.show_info = false;
+        self.show_chapters = false;
+        self.toc_max_depth = max_depth;
+
+        // Seleccionar y centrar la entrada del capítulo actual (o la visible que la contiene,
+        // si está plegada o es demasiado profunda); si no tiene entrada, empezar arriba
+        let current_toc_index = self.current_toc_index();
+        let selected = self.visible_toc_entries().into_iter()
+            .map(|(i, _)| i)
+            .take_while(|&i| current_toc_index.is_some_and(|current| i <= current))
+            .last();
+        let visible: Vec<usize> = self.visible_toc_entries().into_iter().map(|(i, _)| i).collect();
+        self.toc_selected = selected.or_else(|| visible.first().copied()).unwrap_or(0);
+        let current_row = selected.and_then(|selected| visible.iter().position(|&i| i == selected));
+        self.toc_scroll_offset = match current_row {
+            // +1 por la línea de título de la vista
+            Some(row) => (row + 1).saturating_sub(self.content_height as usize / 2) as u16,
+            None => 0,
+        };
+    }
+
+    // Entradas del árbol de la TOC que pasan el filtro de profundidad y no están dentro de
+    // una entrada plegada, con su índice en la lista aplanada (el de su recorrido en preorden)
+    fn visible_toc_entries(&self) -> Vec<(usize, &TocEntry)> {
+        fn visit<'a>(app: &App, entries: &'a [TocEntry], shown: bool, next: &mut usize, visible: &mut Vec<(usize, &'a TocEntry)>) {
+            for entry in entries {
+                let index = *next;
+                *next += 1;
+                if shown {
+                    visible.push((index, entry));
+                }
+                let children_shown = shown && app.toc_shows_children(entry) && !app.toc_collapsed.contains(&index);
+                visit(app, &entry.children, children_shown, next, visible);
+            }
+        }
+        let mut visible = Vec::new();
+        let tree = self.navigator.toc_tree();
+        let shown = self.toc_max_depth.is_none_or(|max_depth| max_depth >= 1);
+        visit(self, tree, shown, &mut 0, &mut visible);
+        visible
+    }
+
+    // Indica si la entrada del árbol de la TOC tiene subentradas que el filtro de
+    // profundidad deja ver (las que se pueden plegar)
+    fn toc_shows_children(&self, entry: &TocEntry) -> bool {
+        !entry.children.is_empty() && self.toc_max_depth.is_none_or(|max_depth| entry.depth < max_depth)
+    }
+
+    // Igual que toc_shows_children, para la entrada visible con ese índice
+    fn toc_has_children(&self, toc_index: usize) -> bool {
+        self.visible_toc_entries().into_iter()
+            .find(|&(i, _)| i == toc_index)
+            .is_some_and(|(_, entry)| self.toc_shows_children(entry))
+    }
+
+    // Mueve la selección de la TOC a la entrada visible siguiente o anterior, desplazando
+    // la vista si la selección se sale de ella
+    fn move_toc_selection(&mut self, forward: bool) {
+        let visible: Vec<usize> = self.visible_toc_entries().into_iter().map(|(i, _)| i).collect();
+        let Some(row) = visible.iter().position(|&i| i == self.toc_selected) else {
+            self.toc_selected = visible.first().copied().unwrap_or(0);
+            return;
+        };
+        let row = match forward {
+            true => (row + 1).min(visible.len() - 1),
+            false => row.saturating_sub(1),
+        };
+        self.toc_selected = visible[row];
+
+        // +1 por la línea de título de la vista
+        let height = self.content_height.max(1) as usize;
+        let line = row + 1;
+        let offset = self.toc_scroll_offset as usize;
+        if line < offset {
+            self.toc_scroll_offset = line as u16;
+        } else if line >= offset + height {
+            self.toc_scroll_offset = (line + 1 - height) as u16;
+        }
+    }
+
+    // Pliega o despliega la entrada seleccionada de la TOC
+    fn toggle_toc_entry(&mut self) {
+        if !self.toc_collapsed.remove(&self.toc_selected) {
+            self.toc_collapsed.insert(self.toc_selected);
+        }
+    }
+
+    // Enter en la TOC: va a la entrada seleccionada. Las que tienen subentradas pero no
+    // llevan a ningún capítulo se pliegan o despliegan en su lugar
+    fn select_toc_entry(&mut self) {
+        let toc_index = self.toc_selected;
+        if self.navigator.spine_index_for_toc(toc_index).is_none() {
+            match self.toc_has_children(toc_index) {
+                true => self.toggle_toc_entry(),
+                false => self.status_message = self.lang.text(Msg::TocEntryWithoutChapter).to_string(),
+            }
+            return;
+        }
+        if self.open_toc_entry(toc_index) {
+            self.show_toc = false;
+            self.status_message = self.lang.format(Msg::Section, &[&self.navigator.get_toc()[toc_index].label]);
+        }
+    }
+
+    // Entrada de la TOC correspondiente al capítulo actual, si existe
+    fn current_toc_index(&self) -> Option<usize> {
+        self.navigator.toc_index_for_spine(self.navigator.current_position().0 - 1)
+    }
+
+    // Completa el nombre del comando con Tab. Si hay varios candidatos,
+    // las pulsaciones sucesivas de Tab los recorren en orden
+    fn complete_command(&mut self) {
+        let (prefix, index) = match self.completion.take() {
+            Some((prefix, index)) => (prefix, index + 1),
+            None => {
+                // Solo se completa la primera palabra (el nombre del comando)
+                if self.command_input.contains(char::is_whitespace) {
+                    return;
+                }
+                (self.command_input.to_lowercase(), 0)
             }
+        };
+
+        let candidates: Vec<&str> = COMMANDS.iter()
+            .copied()
+            .filter(|command| command.starts_with(prefix.as_str()))
+            .collect();
+        if candidates.is_empty() {
+            return;
         }
 
-        self.command_input.clear();
-        self.mode = AppMode::Normal;
+        self.command_input = candidates[index % candidates.len()].to_string();
+        if candidates.len() > 1 {
+            self.completion = Some((prefix, index));
+        }
+    }
+
+    // Recupera el comando anterior del historial (Up)
+    fn history_prev(&mut self) {
+        if self.command_history.is_empty() {
+            return;
+        }
+        let index = match self.history_index {
+            Some(index) => index.saturating_sub(1),
+            None => self.command_history.len() - 1,
+        };
+        self.history_index = Some(index);
+        self.command_input = self.command_history[index].clone();
+        self.completion = None;
+    }
+
+    // Avanza en el historial (Down); pasado el último vuelve a una línea vacía
+    fn history_next(&mut self) {
+        let Some(index) = self.history_index else {
+            return;
+        };
+        if index + 1 < self.command_history.len() {
+            self.history_index = Some(index + 1);
+            self.command_input = self.command_history[index + 1].clone();
+        } else {
+            self.history_index = None;
+            self.command_input.clear();
+        }
+        self.completion = None;
     }
 
     // Maneja eventos de teclado
     pub fn handle_key_event(&mut self, key: KeyCode, modifiers: KeyModifiers) {
         match self.mode {
             AppMode::Normal => {
-                if self.show_toc {
+                if self.image_popup.is_some() {
+                    if matches!(key, KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char('i')) {
+                        self.image_popup = None;
+                        self.status_message = self.chapter_status();
+                    }
+                } else if self.footnote_popup.is_some() {
+                    // La nota emergente se cierra con Esc (o Enter/q) sin afectar a la lectura
+                    if matches!(key, KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q')) {
+                        self.footnote_popup = None;
+                        self.status_message = self.chapter_status();
+                    }
+                } else if self.show_toc {
                     // Manejo específico para la tabla de contenidos
+                    match key {
+                        KeyCode::Char('j') | KeyCode::Down => self.move_toc_selection(true),
+                        KeyCode::Char('k') | KeyCode::Up => self.move_toc_selection(false),
+                        KeyCode::Char(' ') if self.toc_has_children(self.toc_selected) => self.toggle_toc_entry(),
+                        KeyCode::Enter => self.select_toc_entry(),
+                        KeyCode::Esc => {
+                            self.show_toc = false;
+                            self.toc_scroll_offset = 0;
+                        }
+                        _ => {}
+                    }
+                } else if self.show_metadata {
+                    // La vista de metadatos se desplaza igual que la TOC
                     match key {
                         KeyCode::Char('j') => {
-                            self.toc_scroll_offset = self.toc_scroll_offset.saturating_add(1);
+                            self.meta_scroll_offset = self.meta_scroll_offset.saturating_add(1);
                         }
                         KeyCode::Char('k') => {
-                            self.toc_scroll_offset = self.toc_scroll_offset.saturating_sub(1);
+                            self.meta_scroll_offset = self.meta_scroll_offset.saturating_sub(1);
                         }
                         KeyCode::Esc => {
-                            self.show_toc = false;
-                            self.toc_scroll_offset = 0;
+                            self.show_metadata = false;
+                        }
+                        _ => {}
+                    }
+                } else if self.show_chapters {
+                    // Lista de capítulos: se elige uno y Enter lo abre
+                    match key {
+                        KeyCode::Char('j') | KeyCode::Down => {
+                            let last = self.navigator.total_chapters().saturating_sub(1);
+                            self.chapters_selected = (self.chapters_selected + 1).min(last);
+                        }
+                        KeyCode::Char('k') | KeyCode::Up => {
+                            self.chapters_selected = self.chapters_selected.saturating_sub(1);
+                        }
+                        KeyCode::Enter => {
+                            self.show_chapters = false;
+                            self.goto_chapter(self.chapters_selected + 1);
+                        }
+                        KeyCode::Esc => {
+                            self.show_chapters = false;
                         }
                         _ => {}
                     }
@@ -197,16 +1743,24 @@ impl<'a> App<'a> {
                     // Manejo para el contenido del capítulo
                     match key {
                         KeyCode::Char('j') => {
-                            self.scroll_offset = self.scroll_offset.saturating_add(1);
+                            self.scroll_down(self.scroll_step);
                         }
                         KeyCode::Char('k') => {
-                            self.scroll_offset = self.scroll_offset.saturating_sub(1);
+                            self.scroll_up(self.scroll_step);
                         }
                         KeyCode::Char('d') if modifiers.contains(KeyModifiers::CONTROL) => {
-                            self.scroll_offset = self.scroll_offset.saturating_add(10);
+                            let previous = self.scroll_offset.min(self.max_scroll());
+                            self.scroll_offset = previous.saturating_add(self.half_page()).min(self.max_scroll());
+                            self.count_lines_read((self.scroll_offset - previous) as usize);
                         }
                         KeyCode::Char('u') if modifiers.contains(KeyModifiers::CONTROL) => {
-                            self.scroll_offset = self.scroll_offset.saturating_sub(10);
+                            self.scroll_offset = self.scroll_offset.min(self.max_scroll()).saturating_sub(self.half_page());
+                        }
+                        KeyCode::Char(' ') if modifiers.contains(KeyModifiers::SHIFT) => {
+                            self.page_up();
+                        }
+                        KeyCode::Char(' ') => {
+                            self.page_down();
                         }
                         KeyCode::Char('g') if modifiers.contains(KeyModifiers::SHIFT) => {
                             self.scroll_offset = u16::MAX; // Ir al final del texto
@@ -214,23 +1768,92 @@ impl<'a> App<'a> {
                         KeyCode::Char('g') => {
                             self.scroll_offset = 0; // Ir al inicio del texto
                         }
+                        KeyCode::Char('n') if self.has_search_results() => {
+                            self.cycle_search(true);
+                        }
+                        KeyCode::Char('N') if self.has_search_results() => {
+                            self.cycle_search(false);
+                        }
                         KeyCode::Char('n') => {
                             self.next_chapter();
                         }
+                        KeyCode::Home => {
+                            self.first_chapter();
+                        }
+                        KeyCode::End => {
+                            self.last_chapter();
+                        }
+                        KeyCode::Char('z') => {
+                            self.toggle_zen_mode();
+                        }
+                        KeyCode::Char('i') => {
+                            self.open_highlighted_image();
+                        }
+                        KeyCode::Char('s') => {
+                            self.toggle_auto_scroll();
+                        }
+                        KeyCode::Char('+') => {
+                            self.change_wpm(WPM_STEP);
+                        }
+                        KeyCode::Char('-') => {
+                            self.change_wpm(-WPM_STEP);
+                        }
+                        // Las flechas siguen el sentido de lectura: en libros RTL
+                        // la página siguiente está a la izquierda
+                        KeyCode::Right | KeyCode::Left => {
+                            let rtl = self.epub_doc.reading_direction == ReadingDirection::Rtl;
+                            if (key == KeyCode::Right) != rtl {
+                                self.next_chapter();
+                            } else {
+                                self.prev_chapter();
+                            }
+                        }
                         KeyCode::Char('p') => {
                             self.prev_chapter();
                         }
+                        KeyCode::Char(']') => {
+                            self.jump_section(true);
+                        }
+                        KeyCode::Char('[') => {
+                            self.jump_section(false);
+                        }
+                        KeyCode::Char('/') => {
+                            self.start_search();
+                        }
                         KeyCode::Char(':') => {
                             self.mode = AppMode::Command;
                             self.command_input.clear();
                         }
                         KeyCode::Char('q') => {
-                            self.should_quit = true;
+                            self.quit(false);
+                        }
+                        KeyCode::Char('y') => {
+                            match self.current_line() {
+                                Some(line) => self.copy_to_clipboard(&line, Msg::LineCopied),
+                                None => self.status_message = self.lang.text(Msg::NoLineToCopy).to_string(),
+                            }
+                        }
+                        KeyCode::Enter if self.offer_skip => {
+                            self.next_chapter();
+                        }
+                        KeyCode::Enter => {
+                            self.follow_link();
+                        }
+                        KeyCode::Char('o') if modifiers.contains(KeyModifiers::CONTROL) => {
+                            self.go_back();
                         }
                         KeyCode::Esc => {
                             // Salir de vistas especiales (TOC o metadata)
                             self.show_toc = false;
                             self.show_metadata = false;
+                            self.show_landmarks = false;
+                            self.show_info = false;
+                            self.show_chapters = false;
+                            // ...y dejar de recorrer la búsqueda: n vuelve a pasar de capítulo
+                            if self.has_search_results() {
+                                self.clear_search_results();
+                                self.status_message = self.chapter_status();
+                            }
                         }
                         _ => {}
                     }
@@ -240,29 +1863,84 @@ impl<'a> App<'a> {
                 KeyCode::Enter => {
                     self.process_command();
                 }
+                KeyCode::Tab => {
+                    self.complete_command();
+                }
+                KeyCode::Up => {
+                    self.history_prev();
+                }
+                KeyCode::Down => {
+                    self.history_next();
+                }
                 KeyCode::Char(c) => {
                     self.command_input.push(c);
+                    self.completion = None;
                 }
                 KeyCode::Backspace => {
                     self.command_input.pop();
+                    self.completion = None;
                 }
                 KeyCode::Esc => {
                     self.command_input.clear();
+                    self.history_index = None;
+                    self.completion = None;
                     self.mode = AppMode::Normal;
                 }
                 _ => {}
             },
+            AppMode::Search { ref mut query, .. } => match key {
+                KeyCode::Enter => self.finish_search(true),
+                KeyCode::Esc => self.finish_search(false),
+                KeyCode::Char(c) => {
+                    query.push(c);
+                    self.update_search();
+                }
+                KeyCode::Backspace => {
+                    query.pop();
+                    self.update_search();
+                }
+                _ => {}
+            },
+        }
+    }
+}
+
+// Navegador del libro. Las entradas de una TOC generada a partir del spine llegan sin
+// etiqueta: se llaman "Capítulo N" hasta que se lee su título (ver load_current_chapter)
+fn create_navigator(epub_doc: &EpubDocument, lang: Lang) -> Navigator {
+    let mut navigator = epub_doc.create_navigator();
+    if epub_doc.toc_source == TocSource::Spine {
+        for toc_index in 0..navigator.get_toc().len() {
+            navigator.set_toc_label(toc_index, format!("{} {}", lang.text(Msg::Chapter), toc_index + 1));
         }
     }
+    navigator
+}
+
+// Sustituye un "~" inicial por el directorio personal del usuario
+fn expand_tilde(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            PathBuf::from(home).join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+// Convierte un título en un nombre de archivo seguro: solo letras, dígitos, '-' y '_'.
+// Si no queda nada se usa `fallback`
+fn sanitize_file_name(name: &str, fallback: &str) -> String {
+    let sanitized: String = name.trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    if sanitized.is_empty() { fallback.to_string() } else { sanitized }
 }
 
 // Función para ejecutar la UI
 pub fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<()> {
-    // Cargar el primer capítulo
-    app.load_current_chapter();
-
     loop {
-        terminal.draw(|f| ui::<B>(f, app))?;
+        terminal.draw(|f| ui(f, app))?;
 
         if event::poll(Duration::from_millis(100))? {
             if let Event::Key(key) = event::read()? {
@@ -271,189 +1949,637 @@ pub fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Res
                 }
             }
         }
+        app.auto_scroll_tick();
 
+        // La posición ya se guardó (o se descartó a sabiendas) en App::quit
         if app.should_quit {
             return Ok(());
         }
     }
 }
 
+// Estado del selector de libros recientes que se muestra al arrancar sin archivo
+struct RecentPicker {
+    recent: RecentBooks,
+    selected: usize,
+    message: String,
+    lang: Lang,
+}
+
+// Muestra los libros recientes hasta que el usuario abre uno (Enter) o sale (q/Esc)
+fn run_picker<B: Backend>(terminal: &mut Terminal<B>, theme: &Theme, lang: Lang) -> io::Result<Option<(EpubDocument, PathBuf)>> {
+    let mut picker = RecentPicker {
+        recent: RecentBooks::load(),
+        selected: 0,
+        message: lang.text(Msg::PickerHelp).to_string(),
+        lang,
+    };
+
+    loop {
+        terminal.draw(|f| render_recent(f, f.size(), &picker, theme))?;
+
+        if !event::poll(Duration::from_millis(100))? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != event::KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(None),
+            KeyCode::Char('j') | KeyCode::Down if picker.selected + 1 < picker.recent.books.len() => {
+                picker.selected += 1;
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                picker.selected = picker.selected.saturating_sub(1);
+            }
+            KeyCode::Enter => {
+                let Some(book) = picker.recent.books.get(picker.selected) else {
+                    continue;
+                };
+                if !book.exists() {
+                    picker.message = lang.format(Msg::FileMissing, &[&book.path.display()]);
+                    continue;
+                }
+                match EpubDocument::open(&book.path) {
+                    Ok(doc) => return Ok(Some((doc, book.path.clone()))),
+                    Err(e) => picker.message = lang.format(Msg::OpenFailed, &[&book.path.display(), &e]),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Función para renderizar el selector de libros recientes
+fn render_recent(f: &mut Frame<'_>, area: Rect, picker: &RecentPicker, theme: &Theme) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),  // Título
+            Constraint::Min(1),     // Lista
+            Constraint::Length(1),  // Mensajes
+        ])
+        .split(area);
+
+    let title_widget = Paragraph::new(picker.lang.text(Msg::RecentBooks))
+        .style(theme.bar());
+    f.render_widget(title_widget, chunks[0]);
+
+    let mut lines = Vec::new();
+    if picker.recent.books.is_empty() {
+        lines.push(Line::from(picker.lang.text(Msg::NoRecentBooks)));
+    }
+    for (i, book) in picker.recent.books.iter().enumerate() {
+        let mut style = Style::default();
+        // Los archivos que ya no existen se muestran atenuados
+        if !book.exists() {
+            style = style.fg(theme.dimmed);
+        }
+        if i == picker.selected {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+        lines.push(Line::from(vec![
+            Span::raw(format!("{:>3}. ", i + 1)),
+            Span::raw(book.title.clone()),
+            Span::raw(format!(" {} ", picker.lang.format(Msg::RecentChapter, &[&book.chapter]))),
+            Span::styled(book.path.display().to_string(), Style::default().add_modifier(Modifier::DIM)),
+        ]).style(style));
+    }
+
+    // Mantener visible el elemento seleccionado
+    let scroll = picker.selected.saturating_sub(chunks[1].height.saturating_sub(1) as usize) as u16;
+    let list_widget = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::NONE))
+        .style(theme.base())
+        .scroll((scroll, 0));
+    f.render_widget(list_widget, chunks[1]);
+
+    let message_widget = Paragraph::new(picker.message.clone())
+        .style(theme.bar());
+    f.render_widget(message_widget, chunks[2]);
+}
+
 // Función para renderizar la UI
-fn ui<B: Backend>(f: &mut Frame<'_>, app: &App) {
+fn ui(f: &mut Frame<'_>, app: &mut App) {
     let size = f.size();
 
-    // Crear el layout principal
+    // Crear el layout principal. En modo zen las barras desaparecen, salvo la inferior
+    // mientras se escribe un comando o una búsqueda
+    let top_bar = if app.zen_mode { 0 } else { 1 };
+    let bottom_bar = if app.zen_mode && matches!(app.mode, AppMode::Normal) { 0 } else { 1 };
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(1),  // Barra de estado superior
-            Constraint::Min(1),     // Contenido principal
-            Constraint::Length(1),  // Barra de estado inferior o entrada de comando
+            Constraint::Length(top_bar),    // Barra de estado superior
+            Constraint::Min(1),             // Contenido principal
+            Constraint::Length(bottom_bar), // Barra de estado inferior o entrada de comando
         ])
         .split(size);
 
     // Renderizar la barra de estado superior
-    let title = match app.navigator.current_position() {
-        (current, total) => format!("EPUB Reader - Capítulo {} de {}", current, total),
-    };
+    let (current, total) = app.navigator.current_position();
+    let title = format!("EPUB Reader - {} {} {} {}", app.lang.text(Msg::Chapter), current, app.lang.text(Msg::Of), total);
     let title_widget = Paragraph::new(title)
-        .style(Style::default().bg(Color::Blue).fg(Color::White));
+        .style(app.theme.bar());
     f.render_widget(title_widget, chunks[0]);
 
     // Renderizar el contenido principal
     if app.show_metadata {
-        render_metadata::<B>(f, chunks[1], &app.epub_doc.metadata);
+        render_metadata(f, chunks[1], app);
+    } else if app.show_landmarks {
+        render_landmarks(f, chunks[1], app);
+    } else if app.show_info {
+        render_info(f, chunks[1], app);
+    } else if app.show_chapters {
+        render_chapters(f, chunks[1], app);
     } else if app.show_toc {
-        render_toc::<B>(f, chunks[1], app);
+        render_toc(f, chunks[1], app);
     } else {
-        render_content::<B>(f, chunks[1], app);
+        render_content(f, chunks[1], app);
+    }
+    if let Some(note) = &app.footnote_popup {
+        render_footnote(f, chunks[1], note, &app.theme, app.lang);
+    }
+    if let Some(image) = &app.image_popup {
+        render_image(f, chunks[1], image, &app.theme, app.lang);
     }
 
     // Renderizar la barra inferior
     match app.mode {
         AppMode::Normal => {
+            // El progreso del capítulo solo tiene sentido en la vista de lectura
+            let progress = if app.showing_overlay() {
+                String::new()
+            } else if app.auto_scroll {
+                app.lang.format(Msg::AutoScrollProgress, &[&app.wpm, &app.chapter_progress()])
+            } else {
+                app.chapter_progress()
+            };
+            let bar = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([
+                    Constraint::Min(1),
+                    Constraint::Length(progress.width() as u16 + 1),
+                ])
+                .split(chunks[2]);
             let status = Paragraph::new(app.status_message.clone())
-                .style(Style::default().bg(Color::Blue).fg(Color::White));
-            f.render_widget(status, chunks[2]);
+                .style(app.theme.bar());
+            f.render_widget(status, bar[0]);
+            let progress_widget = Paragraph::new(progress)
+                .style(app.theme.bar())
+                .alignment(Alignment::Right);
+            f.render_widget(progress_widget, bar[1]);
         }
         AppMode::Command => {
             let command = format!(":{}", app.command_input);
             let command_widget = Paragraph::new(command)
-                .style(Style::default().bg(Color::Black).fg(Color::White));
+                .style(app.theme.base());
             f.render_widget(command_widget, chunks[2]);
         }
+        AppMode::Search { ref query, .. } => {
+            // Consulta a la izquierda y, a la derecha, las coincidencias y las teclas propias de la búsqueda
+            let lang = app.lang;
+            let keys = format!("{}  {}", lang.text(Msg::SearchAccept), lang.text(Msg::SearchBack));
+            let hint = match app.search_matches.len() {
+                _ if query.is_empty() => keys,
+                0 => format!("{}  {}", lang.text(Msg::NoMatches), lang.text(Msg::SearchBack)),
+                1 => format!("1 {}  {}", lang.text(Msg::OneMatch), keys),
+                count => format!("{} {}  {}", count, lang.text(Msg::Matches), keys),
+            };
+            let bar = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Min(0), Constraint::Length(hint.width() as u16)])
+                .split(chunks[2]);
+            let search_widget = Paragraph::new(format!("/{}", query))
+                .style(app.theme.base());
+            f.render_widget(search_widget, bar[0]);
+            let hint_widget = Paragraph::new(hint)
+                .style(app.theme.base().add_modifier(Modifier::DIM))
+                .alignment(Alignment::Right);
+            f.render_widget(hint_widget, bar[1]);
+        }
     }
 }
 
 // Función para renderizar el contenido del capítulo
-fn render_content<B: Backend>(f: &mut Frame<'_>, area: Rect, app: &App) {
-    // Justificar el texto para que se ajuste al ancho del área
+fn render_content(f: &mut Frame<'_>, area: Rect, app: &mut App) {
+    // La barra de desplazamiento ocupa su propia columna para no pisar el resaltado
+    let scrollbar_area = area;
+    let area = match app.show_scrollbar {
+        true if area.width > 1 => Rect { width: area.width - 1, ..area },
+        _ => area,
+    };
+
+    // Limitar el ancho de lectura y centrar el bloque de texto dejando márgenes
+    let area = match app.max_line_width {
+        Some(max_width) if (max_width as u16) < area.width => {
+            let margin = (area.width - max_width as u16) / 2;
+            Rect { x: area.x + margin, width: max_width as u16, ..area }
+        }
+        _ => area,
+    };
+
+    // Justificar el texto para que se ajuste al ancho del área. El resultado se guarda
+    // y solo se recalcula si cambia el capítulo o el ancho, no en cada fotograma
     let width = area.width as usize;
-    let justified_text = justify_text(&app.current_content, width);
-    
-    // Convertir el Text a un vector de Lines para poder modificar el estilo de la línea actual
-    let mut lines = justified_text.lines.clone();
+    if app.content_dirty || app.wrapped_width != width {
+        // Si solo cambia el ancho (el terminal se redimensionó), se recuerda qué texto estaba
+        // arriba para que siga en pantalla tras volver a ajustarlo
+        let top = (!app.content_dirty && app.wrapped_width != 0).then(|| app.top_text_position());
+        app.wrapped_lines = justify_text(&app.current_content, &app.styles, width, app.rtl, &app.theme);
+        app.wrapped_width = width;
+        app.content_dirty = false;
+        if let Some(top) = top {
+            app.content_width = area.width;
+            app.scroll_offset = app.row_for_text_position(top).min(u16::MAX as usize) as u16;
+        }
+    }
+
+    // Guardar las dimensiones para que la navegación por páginas sepa cuánto avanzar
+    app.content_height = area.height;
+    app.content_width = area.width;
+    app.content_line_count = app.wrapped_lines.len();
+    app.scroll_offset = app.scroll_offset.min(app.max_scroll());
     
-    // Calcular la altura visible del área de contenido
+    // Solo se copian las líneas visibles, para poder modificar el estilo de la línea actual
     let visible_height = area.height as usize;
-    
-    // Calcular la línea que debe estar en el centro de la pantalla
-    let middle_line_idx = visible_height / 2;
+    let first_visible = (app.scroll_offset as usize).min(app.wrapped_lines.len());
+    let last_visible = (first_visible + visible_height).min(app.wrapped_lines.len());
+    let mut lines = app.wrapped_lines[first_visible..last_visible].to_vec();
     
     // Siempre resaltar la línea del medio de la pantalla visible
-    if let Some(middle_line) = lines.get_mut(app.scroll_offset as usize + middle_line_idx) {
+    if let Some(middle_line) = lines.get_mut(visible_height / 2) {
         // Resaltar la línea central con un fondo gris oscuro
-        let spans = middle_line.spans.clone();
-        *middle_line = Line::from(spans).style(Style::default().bg(Color::Rgb(40, 40, 40)));
+        middle_line.style = middle_line.style.bg(app.theme.highlight);
     }
     
     let highlighted_text = Text::from(lines);
 
     let text_widget = Paragraph::new(highlighted_text)
         .block(Block::default().borders(Borders::NONE))
-        .scroll((app.scroll_offset, 0))
-        .wrap(Wrap { trim: true });
+        .style(app.theme.base())
+        // Sin recortar: las líneas ya vienen ajustadas y así se conserva la sangría
+        .wrap(Wrap { trim: false });
 
     f.render_widget(text_widget, area);
+    if app.show_scrollbar {
+        render_scrollbar(f, scrollbar_area, app.scroll_offset as usize, app.max_scroll() as usize, &app.theme);
+    }
+}
+
+// Barra de desplazamiento vertical en la columna derecha del área. Solo se dibuja si
+// hay algo que desplazar (max_position > 0)
+fn render_scrollbar(f: &mut Frame<'_>, area: Rect, position: usize, max_position: usize, theme: &Theme) {
+    if max_position == 0 {
+        return;
+    }
+    let mut state = ScrollbarState::new(max_position + 1)
+        .position(position.min(max_position))
+        .viewport_content_length(area.height as usize);
+    let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+        .begin_symbol(None)
+        .end_symbol(None)
+        .style(Style::default().fg(theme.dimmed));
+    f.render_stateful_widget(scrollbar, area, &mut state);
+}
+
+// Ventana emergente con una nota al pie, centrada sobre el texto
+fn render_footnote(f: &mut Frame<'_>, area: Rect, note: &str, theme: &Theme, lang: Lang) {
+    let width = (area.width * 3 / 4).max(20).min(area.width);
+    let inner_width = width.saturating_sub(2).max(1) as usize;
+    let text_height = fill(note, inner_width).lines().count() as u16;
+    let height = (text_height + 2).min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+
+    let note_widget = Paragraph::new(note.to_string())
+        .block(Block::default().borders(Borders::ALL).title(format!(" {} ", lang.text(Msg::Note))))
+        .style(theme.base())
+        .wrap(Wrap { trim: true });
+
+    f.render_widget(Clear, popup);
+    f.render_widget(note_widget, popup);
+}
+
+// Ventana emergente con una imagen ya convertida en arte de bloques, centrada sobre el texto
+fn render_image(f: &mut Frame<'_>, area: Rect, image: &[Line<'static>], theme: &Theme, lang: Lang) {
+    let image_width = image.iter().map(Line::width).max().unwrap_or(0) as u16;
+    let width = (image_width + 2).min(area.width);
+    let height = (image.len() as u16 + 2).min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+
+    let image_widget = Paragraph::new(image.to_vec())
+        .block(Block::default().borders(Borders::ALL).title(format!(" {} ", lang.text(Msg::Image))))
+        .style(theme.base());
+
+    f.render_widget(Clear, popup);
+    f.render_widget(image_widget, popup);
 }
 
 // Función para renderizar la tabla de contenidos
-fn render_toc<B: Backend>(f: &mut Frame<'_>, area: Rect, app: &App) {
+fn render_toc(f: &mut Frame<'_>, area: Rect, app: &App) {
+    let scrollbar_area = area;
+    let area = match app.show_scrollbar {
+        true if area.width > 1 => Rect { width: area.width - 1, ..area },
+        _ => area,
+    };
+
     let mut toc_text = vec![Line::from(vec![
-        Span::styled("Tabla de Contenidos", Style::default().add_modifier(Modifier::BOLD))
+        Span::styled(app.lang.text(Msg::TableOfContents), Style::default().add_modifier(Modifier::BOLD))
     ])];
 
-    for (i, entry) in app.navigator.get_toc().iter().enumerate() {
+    let current_toc_index = app.current_toc_index();
+    for (i, entry) in app.visible_toc_entries() {
+        // Sangría por nivel y marcador de plegado en las entradas con subentradas
+        let marker = match app.toc_shows_children(entry) {
+            true if app.toc_collapsed.contains(&i) => "\u{25b8} ",
+            true => "\u{25be} ",
+            false => "  ",
+        };
+        let indent = "  ".repeat(entry.depth.saturating_sub(1));
         let line = Line::from(vec![
-            Span::raw(format!("{:>3}. ", i + 1)),
+            Span::raw(format!("{:>3}. {}{}", i + 1, indent, marker)),
             Span::raw(&entry.label),
         ]);
-        toc_text.push(line);
+        // Resaltar dónde está el lector (negrita) y la selección (invertida)
+        let mut style = Style::default();
+        if Some(i) == current_toc_index {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        if i == app.toc_selected {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+        toc_text.push(line.style(style));
     }
+    let toc_text_len = toc_text.len();
 
     let toc_widget = Paragraph::new(toc_text)
         .block(Block::default().borders(Borders::NONE))
+        .style(app.theme.base())
         // Usar el offset específico para la TOC
         .scroll((app.toc_scroll_offset, 0))
         .wrap(Wrap { trim: true });
 
+    // Líneas sin ajustar: basta para orientarse aunque alguna entrada larga ocupe dos
+    let max_scroll = toc_text_len.saturating_sub(area.height as usize);
     f.render_widget(toc_widget, area);
+    if app.show_scrollbar {
+        render_scrollbar(f, scrollbar_area, app.toc_scroll_offset as usize, max_scroll, &app.theme);
+    }
+}
+
+// Lista de puntos de referencia del libro con el capítulo en el que está cada uno
+fn render_landmarks(f: &mut Frame<'_>, area: Rect, app: &App) {
+    let mut landmark_text = vec![Line::from(vec![
+        Span::styled(app.lang.text(Msg::Landmarks), Style::default().add_modifier(Modifier::BOLD))
+    ])];
+
+    for landmark in &app.epub_doc.landmarks {
+        let label = if landmark.label.is_empty() { &landmark.kind } else { &landmark.label };
+        let mut spans = vec![Span::raw(format!("  {}", label))];
+        if !landmark.kind.is_empty() && landmark.kind != *label {
+            spans.push(Span::styled(format!(" ({})", landmark.kind), Style::default().fg(app.theme.dimmed)));
+        }
+        if let Some(spine_index) = app.navigator.spine_index_for_href(&landmark.href) {
+            spans.push(Span::raw(format!(" — {}", app.lang.format(Msg::OpenWith, &[&(spine_index + 1)]))));
+        }
+        landmark_text.push(Line::from(spans));
+    }
+
+    let landmarks_widget = Paragraph::new(landmark_text)
+        .block(Block::default().borders(Borders::NONE))
+        .style(app.theme.base())
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(landmarks_widget, area);
+}
+
+// Diagnóstico del parseo del libro: rutas, tamaños, origen de la TOC y advertencias
+fn render_info(f: &mut Frame<'_>, area: Rect, app: &App) {
+    let doc = &app.epub_doc;
+    let lang = app.lang;
+    let toc_source = match doc.toc_source {
+        TocSource::Nav => "nav.xhtml (EPUB 3)",
+        TocSource::Ncx => "toc.ncx (EPUB 2)",
+        TocSource::Spine => lang.text(Msg::TocFromSpine),
+        TocSource::None => lang.text(Msg::TocNone),
+    };
+    let linear = doc.spine_linear.iter().filter(|&&linear| linear).count();
+    let root = if doc.root_path.is_empty() { lang.text(Msg::ZipRoot) } else { &doc.root_path };
+    let mut info_text = vec![
+        Line::from(Span::styled(lang.text(Msg::EpubInfo), Style::default().add_modifier(Modifier::BOLD))),
+        Line::from(lang.format(Msg::OpfFile, &[&doc.opf_path.display()])),
+        Line::from(lang.format(Msg::RootDirectory, &[&root])),
+        Line::from(format!("{}: {}", lang.text(Msg::EpubVersion), doc.epub_version.as_deref().unwrap_or(lang.text(Msg::NotAvailable)))),
+        Line::from(lang.format(Msg::ManifestItems, &[&doc.manifest.len()])),
+        Line::from(lang.format(Msg::SpineItems, &[&doc.spine_ids.len(), &linear])),
+        Line::from(lang.format(Msg::TocLine, &[&toc_source, &app.navigator.get_toc().len()])),
+        Line::from(""),
+    ];
+    let warnings = app.warnings();
+    if warnings.is_empty() {
+        info_text.push(Line::from(lang.text(Msg::NoWarnings)));
+    } else {
+        info_text.push(Line::from(Span::styled(lang.text(Msg::Warnings), Style::default().add_modifier(Modifier::BOLD))));
+        info_text.extend(warnings.iter().map(|warning| Line::from(format!("  - {}", warning))));
+    }
+
+    let info_widget = Paragraph::new(info_text)
+        .block(Block::default().borders(Borders::NONE))
+        .style(app.theme.base())
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(info_widget, area);
+}
+
+// Lista de todos los items del spine en orden de lectura, con su entrada de la TOC (si la
+// tiene) y su número de palabras, o "…" mientras se cuentan (ver WordCounter)
+fn render_chapters(f: &mut Frame<'_>, area: Rect, app: &App) {
+    let mut chapter_text = vec![Line::from(vec![
+        Span::styled(app.lang.text(Msg::Chapters), Style::default().add_modifier(Modifier::BOLD))
+    ])];
+
+    // Mantener la selección a la vista (una fila la ocupa el título)
+    let rows = (area.height as usize).saturating_sub(1).max(1);
+    let total = app.navigator.total_chapters();
+    let first = app.chapters_selected.saturating_sub(rows / 2).min(total.saturating_sub(rows));
+    let current = app.navigator.current_position().0 - 1;
+    for spine_index in first..(first + rows).min(total) {
+        // Tiempo estimado a la velocidad de lectura actual (la misma del auto-scroll)
+        let words = match app.word_counter.as_ref().and_then(|counter| counter.get(spine_index)) {
+            Some(Some(count)) => {
+                let minutes = (count as u64).div_ceil(app.wpm as u64);
+                format!("{:>25}", app.lang.format(Msg::WordsAndMinutes, &[&format!("{:>7}", count), &format!("{:>4}", minutes)]))
+            }
+            Some(None) => format!("{:>25}", app.lang.text(Msg::Unreadable)),
+            None => format!("{:>25}", "…"), // Aún contándose en segundo plano
+        };
+        let label = app.navigator.toc_label_for_spine(spine_index)
+            .map(str::to_string)
+            .or_else(|| app.navigator.spine_href(spine_index).ok())
+            .unwrap_or_default();
+        let mut line = Line::from(vec![
+            Span::raw(format!("{:>4}. ", spine_index + 1)),
+            Span::styled(words, Style::default().fg(app.theme.dimmed)),
+            Span::raw(format!("  {}", label)),
+        ]);
+        if spine_index == app.chapters_selected {
+            line = line.style(Style::default().add_modifier(Modifier::REVERSED));
+        } else if spine_index == current {
+            line = line.style(Style::default().add_modifier(Modifier::BOLD));
+        }
+        chapter_text.push(line);
+    }
+
+    let chapters_widget = Paragraph::new(chapter_text)
+        .block(Block::default().borders(Borders::NONE))
+        .style(app.theme.base());
+
+    f.render_widget(chapters_widget, area);
 }
 
 // Función para renderizar los metadatos
-fn render_metadata<B: Backend>(f: &mut Frame<'_>, area: Rect, metadata: &Metadata) {
-    let meta_text = vec![
-        Line::from(vec![
-            Span::styled("Metadatos", Style::default().add_modifier(Modifier::BOLD))
-        ]),
-        Line::from(vec![
-            Span::raw("Título: "),
-            Span::raw(metadata.title.as_deref().unwrap_or("N/A")),
-        ]),
-        Line::from(vec![
-            Span::raw("Autor: "),
-            Span::raw(metadata.creator.as_deref().unwrap_or("N/A")),
-        ]),
-        Line::from(vec![
-            Span::raw("Idioma: "),
-            Span::raw(metadata.language.as_deref().unwrap_or("N/A")),
-        ]),
-        Line::from(vec![
-            Span::raw("Identificador: "),
-            Span::raw(metadata.identifier.as_deref().unwrap_or("N/A")),
-        ]),
+fn render_metadata(f: &mut Frame<'_>, area: Rect, app: &App) {
+    let metadata = &app.epub_doc.metadata;
+    let lang = app.lang;
+    let na = lang.text(Msg::NotAvailable);
+    let field = |label: Msg, value: String| {
         Line::from(vec![
-            Span::raw("Editor: "),
-            Span::raw(metadata.publisher.as_deref().unwrap_or("N/A")),
-        ]),
+            Span::raw(format!("{}: ", lang.text(label))),
+            Span::raw(value),
+        ])
+    };
+    let value = |value: Option<&str>| value.unwrap_or(na).to_string();
+    let mut meta_text = vec![
         Line::from(vec![
-            Span::raw("Fecha: "),
-            Span::raw(metadata.date.as_deref().unwrap_or("N/A")),
+            Span::styled(lang.text(Msg::Metadata), Style::default().add_modifier(Modifier::BOLD))
         ]),
+        field(Msg::Title, value(metadata.title.as_deref())),
+        field(Msg::Author, metadata.creators_display(lang).unwrap_or_else(|| na.to_string())),
+        field(Msg::Language, value(metadata.language.as_deref())),
+        field(Msg::Identifier, value(metadata.identifier.as_deref())),
+        field(Msg::Publisher, value(metadata.publisher.as_deref())),
+        field(Msg::PublicationDate, value(metadata.date.as_deref())),
+        field(Msg::Modified, value(metadata.modified.as_deref())),
+        field(Msg::Series, metadata.series_display().unwrap_or_else(|| na.to_string())),
+        field(Msg::EpubVersion, value(app.epub_doc.epub_version.as_deref())),
+        field(Msg::ScreensRead, app.screens_read.to_string()),
     ];
+    if !metadata.subjects.is_empty() {
+        meta_text.push(field(Msg::Subjects, metadata.subjects.join(", ")));
+    }
+    if let Some(description) = metadata.description.as_deref() {
+        // La descripción suele venir como HTML: se renderiza igual que un capítulo
+        let options = RenderOptions { emphasis_markers: false, ..RenderOptions::default() };
+        meta_text.push(Line::default());
+        meta_text.push(Line::from(vec![
+            Span::styled(lang.text(Msg::Description), Style::default().add_modifier(Modifier::BOLD))
+        ]));
+        let rendered = crate::render::render_xhtml_to_text_with(description, &options);
+        meta_text.extend(rendered.lines().map(|line| Line::from(line.to_string())));
+    }
 
     let meta_widget = Paragraph::new(meta_text)
         .block(Block::default().borders(Borders::NONE))
+        .style(app.theme.base())
+        .scroll((app.meta_scroll_offset, 0))
         .wrap(Wrap { trim: true });
 
     f.render_widget(meta_widget, area);
 }
 
+// Byte del párrafo en el que empieza cada una de sus líneas ajustadas. Las líneas de
+// textwrap son trozos del párrafo, en orden
+fn wrapped_offsets(paragraph: &str, wrapped: &[std::borrow::Cow<'_, str>]) -> Vec<usize> {
+    let mut cursor = 0;
+    wrapped.iter().map(|line| {
+        let offset = paragraph[cursor..].find(line.as_ref()).map_or(cursor, |found| cursor + found);
+        cursor = offset + line.len();
+        offset
+    }).collect()
+}
+
 // Función para justificar el texto
-fn justify_text(text: &str, width: usize) -> Text {
+// Con rtl, las líneas se alinean a la derecha y no se justifican (sin bidi completo).
+// `styles` (ordenados por línea) dan estilo a los fragmentos de cada línea ajustada
+fn justify_text(text: &str, styles: &[StyleRange], width: usize, rtl: bool, theme: &Theme) -> Vec<Line<'static>> {
     let mut justified_lines = Vec::new();
-    
-    // Primero, envolvemos el texto para que se ajuste al ancho
-    let wrapped_text = fill(text, width);
-    
-    // Luego, procesamos cada línea para justificarla
-    for line in wrapped_text.lines() {
+
+    // Cada línea del texto renderizado es un párrafo (o un <br> explícito): se ajusta
+    // al ancho por separado para saber cuál es su última línea, que nunca se justifica.
+    // Se guarda también la posición de cada línea ajustada dentro de su párrafo
+    let wrapped_paragraphs = text.lines().enumerate().map(|(source_line, paragraph)| {
+        let wrapped = wrap(paragraph, width);
+        let offsets = wrapped_offsets(paragraph, &wrapped);
+        let last = wrapped.len().saturating_sub(1);
+        let first_range = styles.partition_point(|range| range.line < source_line);
+        let ranges = &styles[first_range..styles.partition_point(|range| range.line <= source_line)];
+        wrapped.into_iter().zip(offsets).enumerate()
+            .map(move |(index, (line, offset))| (line, offset, ranges, index == last))
+    });
+
+    for (line, offset, ranges, is_last_line) in wrapped_paragraphs.flatten() {
+        let line: &str = &line;
         if line.trim().is_empty() {
             justified_lines.push(Line::from(""));
             continue;
         }
         
-        // Para títulos y listas, no justificamos
-        if line.starts_with('#') || line.starts_with("  -") {
-            justified_lines.push(Line::from(line.to_string()));
+        // Los separadores de escena (<hr>) se centran
+        if line.trim() == SCENE_BREAK {
+            justified_lines.push(Line::from(SCENE_BREAK).alignment(Alignment::Center));
+            continue;
+        }
+
+        let spans = styled_spans(line, offset, ranges, theme);
+        if rtl {
+            justified_lines.push(Line::from(spans).alignment(Alignment::Right));
+            continue;
+        }
+
+        // Para títulos, listas y definiciones sangradas, no justificamos. La sangría de
+        // primera línea de los párrafos (estilo "indent") se conserva y se justifica el resto
+        // Tampoco los versos cortados con <br>: aunque ocupen varias líneas, estirarlos
+        // desdibujaría la forma del poema
+        let is_heading = ranges.iter().any(|range| matches!(range.style, TextStyle::Heading(_)));
+        let is_verse = ranges.iter().any(|range| range.style == TextStyle::Verse);
+        let paragraph_indent = line.strip_prefix(PARAGRAPH_INDENT).is_some_and(|rest| !rest.starts_with(' '));
+        if is_heading || is_verse || line.starts_with('#') || (line.starts_with("  ") && !paragraph_indent) {
+            justified_lines.push(Line::from(spans));
             continue;
         }
         
-        // Justificar la línea si tiene suficiente contenido
+        // Justificar la línea si tiene suficiente contenido y no cierra el párrafo
         let line_width = UnicodeWidthStr::width(line);
-        if line_width > width * 3 / 4 && line_width < width && line.split_whitespace().count() > 1 {
-            let words: Vec<&str> = line.split_whitespace().collect();
+        if !is_last_line && line_width > width * 3 / 4 && line_width < width && line.split_whitespace().count() > 1 {
+            let words = word_positions(line);
             let word_count = words.len();
             
             if word_count > 1 {
-                let total_word_length: usize = words.iter().map(|w| UnicodeWidthStr::width(*w)).sum();
-                let spaces_needed = width - total_word_length;
+                let total_word_length: usize = words.iter().map(|(_, w)| UnicodeWidthStr::width(*w)).sum();
+                let indent = if paragraph_indent { PARAGRAPH_INDENT.len() } else { 0 };
+                let spaces_needed = width - indent - total_word_length;
                 let spaces_between = spaces_needed / (word_count - 1);
                 let extra_spaces = spaces_needed % (word_count - 1);
                 
-                let mut justified_line = String::new();
-                for (i, word) in words.iter().enumerate() {
-                    justified_line.push_str(word);
+                let mut justified_spans = Vec::new();
+                if indent > 0 {
+                    justified_spans.push(Span::raw(PARAGRAPH_INDENT));
+                }
+                for (i, (position, word)) in words.iter().enumerate() {
+                    justified_spans.extend(styled_spans(word, offset + position, ranges, theme));
                     
                     if i < word_count - 1 {
                         let spaces = if i < extra_spaces {
@@ -461,44 +2587,165 @@ fn justify_text(text: &str, width: usize) -> Text {
                         } else {
                             spaces_between
                         };
-                        justified_line.push_str(&" ".repeat(spaces));
+                        justified_spans.push(Span::raw(" ".repeat(spaces)));
                     }
                 }
-                justified_lines.push(Line::from(justified_line));
+                justified_lines.push(Line::from(justified_spans));
             } else {
-                justified_lines.push(Line::from(line.to_string()));
+                justified_lines.push(Line::from(spans));
             }
         } else {
-            justified_lines.push(Line::from(line.to_string()));
+            justified_lines.push(Line::from(spans));
         }
     }
     
-    Text::from(justified_lines)
+    justified_lines
+}
+
+// Palabras de una línea junto con su posición en bytes
+fn word_positions(line: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut word_start = None;
+    for (index, c) in line.char_indices() {
+        match (c.is_whitespace(), word_start) {
+            (true, Some(start)) => {
+                words.push((start, &line[start..index]));
+                word_start = None;
+            }
+            (false, None) => word_start = Some(index),
+            _ => {}
+        }
+    }
+    if let Some(start) = word_start {
+        words.push((start, &line[start..]));
+    }
+    words
+}
+
+// Parte `text` (que empieza en el byte `offset` de su línea original) en fragmentos con
+// el estilo de los tramos que lo cubren. Los tramos pueden anidarse (cursiva dentro de negrita)
+fn styled_spans(text: &str, offset: usize, ranges: &[StyleRange], theme: &Theme) -> Vec<Span<'static>> {
+    let end = offset + text.len();
+    let mut boundaries = vec![offset, end];
+    for range in ranges.iter().filter(|range| range.start < end && range.end > offset) {
+        boundaries.extend([range.start.max(offset), range.end.min(end)]);
+    }
+    boundaries.sort_unstable();
+    boundaries.dedup();
+
+    boundaries.windows(2)
+        .map(|segment| {
+            let style = ranges.iter()
+                .filter(|range| range.start <= segment[0] && range.end >= segment[1])
+                .fold(Style::default(), |style, range| match range.style {
+                    TextStyle::Bold => style.add_modifier(Modifier::BOLD),
+                    TextStyle::Italic => style.add_modifier(Modifier::ITALIC),
+                    TextStyle::Heading(_) => style.add_modifier(Modifier::BOLD).fg(theme.heading),
+                    TextStyle::Verse => style,
+                });
+            Span::styled(text[segment[0] - offset..segment[1] - offset].to_string(), style)
+        })
+        .collect()
+}
+
+// Estilo de párrafo por nombre ("indent" o "spaced"), para la configuración y :paragraphs
+fn paragraph_style_by_name(name: &str) -> Option<ParagraphStyle> {
+    match name {
+        "indent" => Some(ParagraphStyle::Indent),
+        "spaced" => Some(ParagraphStyle::Spaced),
+        _ => None,
+    }
+}
+
+// Marcador de lista por nombre ("dash" o "bullet"), para la configuración
+fn list_style_by_name(name: &str) -> Option<ListStyle> {
+    match name {
+        "dash" => Some(ListStyle::Dash),
+        "bullet" => Some(ListStyle::Bullet),
+        _ => None,
+    }
+}
+
+// Aplica un estilo de texto ("rich", "markdown" o "plain") a las opciones de renderizado.
+// Devuelve si el énfasis se muestra con atributos del terminal (negrita, cursiva, color)
+fn apply_text_style(options: &mut RenderOptions, style: &str) -> bool {
+    let rich = !matches!(style, "markdown" | "plain");
+    options.emphasis_markers = style == "markdown";
+    options.heading_style = if rich { HeadingStyle::Plain } else { HeadingStyle::Hash };
+    rich
+}
+
+// Deja el terminal como estaba antes de arrancar la UI
+fn restore_terminal() -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture, Show)
+}
+
+// Guarda RAII: configura el terminal al crearse y lo restaura al destruirse,
+// tanto al salir normalmente como ante un error con `?` o un pánico
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn new() -> io::Result<Self> {
+        enable_raw_mode()?;
+        if let Err(e) = execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture) {
+            let _ = disable_raw_mode();
+            return Err(e);
+        }
+        Ok(TerminalGuard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = restore_terminal();
+    }
+}
+
+// Restaura el terminal antes de que el hook por defecto imprima el mensaje de pánico,
+// para que el mensaje sea legible y la shell del usuario quede utilizable
+fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal();
+        default_hook(info);
+    }));
 }
 
 // Inicializa el terminal y ejecuta la aplicación
-pub fn start_ui(epub_doc: &mut EpubDocument) -> io::Result<()> {
-    // Configurar el terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
+// Sin libro, primero se muestra el selector de libros recientes
+// start_chapter (basado en 1) abre el libro en ese capítulo; con resume = false se ignora
+// la posición guardada y se empieza donde empezaría un libro nuevo
+pub fn start_ui(
+    book: Option<(EpubDocument, PathBuf)>,
+    config: &AppConfig,
+    start_chapter: Option<usize>,
+    resume: bool,
+) -> io::Result<()> {
+    // Configurar el terminal; se restaura al salir de esta función pase lo que pase
+    install_panic_hook();
+    let _guard = TerminalGuard::new()?;
+    let backend = CrosstermBackend::new(io::stdout());
     let mut terminal = Terminal::new(backend)?;
 
+    let (epub_doc, book_path) = match book {
+        Some(book) => book,
+        None => match run_picker(&mut terminal, &Theme::by_name(&config.theme).unwrap_or_default(), Lang::detect(&config.language))? {
+            Some(book) => book,
+            None => return Ok(()),
+        },
+    };
+
     // Crear la aplicación
-    let mut app = App::new(epub_doc);
+    let mut app = App::new(epub_doc, book_path, config);
 
-    // Ejecutar la aplicación
-    let res = run_app(&mut terminal, &mut app);
+    // Cargar el capítulo inicial: el pedido, la posición guardada o el inicio del texto
+    match start_chapter {
+        Some(chapter) if app.navigator.goto(chapter) => app.load_current_chapter(),
+        _ if resume => app.resume_or_load(),
+        _ => app.load_reading_start(),
+    }
 
-    // Restaurar el terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
-
-    res
+    // Ejecutar la aplicación
+    run_app(&mut terminal, &mut app)
 }
@@ -0,0 +1,90 @@
+// src/ui/prefetch.rs
+// Precarga de capítulos: un hilo lee del ZIP y renderiza los capítulos vecinos del
+// actual mientras se lee, para que pasar de capítulo no espere a descomprimirlos ni a
+// renderizarlos. Lo renderizado vale solo para las opciones con las que se pidió
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::epub::ChapterReader;
+use crate::render::{RenderOptions, RenderedChapter};
+
+#[derive(Default)]
+struct Cache {
+    options: Option<RenderOptions>,              // Opciones con las que se renderizó
+    chapters: HashMap<String, RenderedChapter>, // href -> capítulo ya renderizado
+}
+
+pub struct Prefetcher {
+    cache: Arc<Mutex<Cache>>,
+    requests: Option<Sender<(Vec<String>, RenderOptions)>>, // None si no se pudo abrir el lector
+}
+
+impl Prefetcher {
+    // Arranca el hilo de precarga. Termina solo cuando se descarta el Prefetcher
+    pub fn start(reader: Option<ChapterReader>) -> Self {
+        let cache = Arc::new(Mutex::new(Cache::default()));
+        let requests = reader.map(|mut reader| {
+            let (sender, receiver) = mpsc::channel::<(Vec<String>, RenderOptions)>();
+            let cache = Arc::clone(&cache);
+            thread::spawn(move || {
+                let mut stylesheets = HashMap::new(); // Hojas de estilo ya leídas, por ruta en el ZIP
+                while let Ok(mut request) = receiver.recv() {
+                    // Si llegaron varias peticiones seguidas solo importa la última
+                    while let Ok(newer) = receiver.try_recv() {
+                        request = newer;
+                    }
+                    let (hrefs, options) = request;
+                    // Se descarta lo que ya no es vecino del capítulo actual o se
+                    // renderizó con otras opciones
+                    if let Ok(mut cache) = cache.lock() {
+                        if cache.options.as_ref() != Some(&options) {
+                            cache.chapters.clear();
+                            cache.options = Some(options.clone());
+                        }
+                        cache.chapters.retain(|href, _| hrefs.contains(href));
+                    }
+                    for href in hrefs {
+                        let cached = cache.lock().map_or(true, |cache| cache.chapters.contains_key(&href));
+                        if cached {
+                            continue;
+                        }
+                        // Un error solo significa que ese capítulo se leerá al abrirlo
+                        let Ok(content) = reader.read_chapter_content(&href) else {
+                            continue;
+                        };
+                        let class_styles = crate::render::chapter_class_styles(&href, &content, &mut stylesheets, |path| {
+                            reader.read_entry(path).ok()
+                        });
+                        let rendered = crate::render::render_chapter_with_css(&content, &options, &class_styles);
+                        if let Ok(mut cache) = cache.lock() {
+                            // Las opciones pueden haber cambiado mientras se renderizaba
+                            if cache.options.as_ref() == Some(&options) {
+                                cache.chapters.insert(href, rendered);
+                            }
+                        }
+                    }
+                }
+            });
+            sender
+        });
+        Prefetcher { cache, requests }
+    }
+
+    // Capítulo ya renderizado con estas opciones, si se precargó
+    pub fn take(&self, href: &str, options: &RenderOptions) -> Option<RenderedChapter> {
+        let mut cache = self.cache.lock().ok()?;
+        if cache.options.as_ref() != Some(options) {
+            return None;
+        }
+        cache.chapters.remove(href)
+    }
+
+    // Pide precargar estos capítulos con estas opciones (y olvidar los demás)
+    pub fn request(&self, hrefs: Vec<String>, options: RenderOptions) {
+        if let Some(requests) = &self.requests {
+            let _ = requests.send((hrefs, options));
+        }
+    }
+}
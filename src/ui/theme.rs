@@ -0,0 +1,70 @@
+// src/ui/theme.rs
+use ratatui::style::{Color, Style};
+
+// Colores de la interfaz. Las funciones de renderizado leen de aquí en lugar de usar literales
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub name: String,
+    pub foreground: Color,        // Texto principal
+    pub background: Color,        // Fondo principal
+    pub accent: Color,            // Fondo de las barras de estado
+    pub accent_foreground: Color, // Texto sobre las barras de estado
+    pub highlight: Color,         // Fondo de la línea central resaltada
+    pub dimmed: Color,            // Elementos atenuados (p. ej. libros que ya no existen)
+    pub heading: Color,           // Encabezados del capítulo
+}
+
+impl Theme {
+    // Tema oscuro: respeta los colores por defecto del terminal
+    pub fn dark() -> Self {
+        Theme {
+            name: "dark".to_string(),
+            foreground: Color::Reset,
+            background: Color::Reset,
+            accent: Color::Blue,
+            accent_foreground: Color::White,
+            highlight: Color::Rgb(40, 40, 40),
+            dimmed: Color::DarkGray,
+            heading: Color::Cyan,
+        }
+    }
+
+    // Tema claro: texto oscuro sobre fondo claro, con barras legibles
+    pub fn light() -> Self {
+        Theme {
+            name: "light".to_string(),
+            foreground: Color::Black,
+            background: Color::White,
+            accent: Color::Blue,
+            accent_foreground: Color::White,
+            highlight: Color::Rgb(220, 220, 220),
+            dimmed: Color::Gray,
+            heading: Color::Blue,
+        }
+    }
+
+    // Busca un tema predefinido por nombre
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Theme::dark()),
+            "light" => Some(Theme::light()),
+            _ => None,
+        }
+    }
+
+    // Estilo del texto principal
+    pub fn base(&self) -> Style {
+        Style::default().fg(self.foreground).bg(self.background)
+    }
+
+    // Estilo de las barras de estado superior e inferior
+    pub fn bar(&self) -> Style {
+        Style::default().fg(self.accent_foreground).bg(self.accent)
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::dark()
+    }
+}
@@ -0,0 +1,67 @@
+// src/ui/word_count.rs
+// Recuento de palabras de todos los items del spine para la lista de capítulos
+// (:chapters). Un hilo los lee y renderiza en orden de lectura con su propio acceso al
+// ZIP, de modo que dibujar la lista nunca espera a descomprimir un capítulo
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::epub::ChapterReader;
+use crate::render::RenderOptions;
+
+#[derive(Default)]
+struct Counts {
+    words: Vec<Option<usize>>, // Por item del spine; None si no se pudo leer
+    done: bool,
+}
+
+pub struct WordCounter {
+    counts: Arc<Mutex<Counts>>,
+}
+
+impl WordCounter {
+    // Empieza a contar las palabras de estos capítulos, en este orden
+    pub fn start(reader: Option<ChapterReader>, hrefs: Vec<String>) -> Self {
+        let counts = Arc::new(Mutex::new(Counts::default()));
+        match reader {
+            Some(mut reader) => {
+                let counts = Arc::clone(&counts);
+                thread::spawn(move || {
+                    for href in hrefs {
+                        let count = reader.read_chapter_content(&href).ok().map(|content| count_words(&content));
+                        match counts.lock() {
+                            Ok(mut counts) => counts.words.push(count),
+                            Err(_) => return,
+                        }
+                    }
+                    if let Ok(mut counts) = counts.lock() {
+                        counts.done = true;
+                    }
+                });
+            }
+            // Sin lector no se puede contar ninguno
+            None => {
+                if let Ok(mut counts) = counts.lock() {
+                    counts.words = vec![None; hrefs.len()];
+                    counts.done = true;
+                }
+            }
+        }
+        WordCounter { counts }
+    }
+
+    // Some(palabras) o Some(None) si ya se intentó contar el item; None si aún no
+    pub fn get(&self, spine_index: usize) -> Option<Option<usize>> {
+        let counts = self.counts.lock().ok()?;
+        match counts.words.get(spine_index) {
+            Some(&count) => Some(count),
+            None if counts.done => Some(None),
+            None => None,
+        }
+    }
+}
+
+// Palabras del texto de un capítulo, sin contar las marcas de énfasis
+pub fn count_words(xhtml: &str) -> usize {
+    let options = RenderOptions { emphasis_markers: false, ..RenderOptions::default() };
+    crate::render::render_xhtml_to_text_with(xhtml, &options).split_whitespace().count()
+}